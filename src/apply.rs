@@ -0,0 +1,235 @@
+//! 声明式应用模块
+//!
+//! 配置文件描述"这台机器应该显示哪个Logo"，与固件变量和ESP中的文件比较，
+//! 只在不一致时写入，重复执行时报告 `unchanged`
+//!
+//! 配置文件格式（TOML）：
+//!
+//! ```toml
+//! # 图片路径，相对路径相对于配置文件所在目录
+//! image = "logo.png"
+//! # 超过固件最大分辨率时的处理方式："none"（报错，默认）或 "fit"（等比缩小）
+//! resize = "fit"
+//! # 是否显示Windows加载图标，不设置时保持现状
+//! loading_icon = false
+//! # 是否完全重新生成图片（清洗模式），默认true
+//! sanitize = true
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::esp_partition::read_logo_files;
+use crate::imaging::{self, ResizePolicy};
+use crate::lenlogo::{PlatformInfo, VerifyStatus};
+use crate::platform::{NativePlatform, PlatformOps};
+use crate::profiles;
+use crate::state;
+use crate::status::SCHEMA_VERSION;
+
+/// 期望状态配置
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApplyConfig {
+    /// 图片路径
+    pub image: PathBuf,
+    /// 超过固件最大分辨率时的处理方式
+    #[serde(default)]
+    pub resize: ResizePolicy,
+    /// 是否显示Windows加载图标，`None` 表示不管理
+    pub loading_icon: Option<bool>,
+    /// 是否完全重新生成图片（清洗模式），默认开启
+    #[serde(default = "default_sanitize")]
+    pub sanitize: bool,
+}
+
+/// 清洗模式默认开启
+pub(crate) fn default_sanitize() -> bool {
+    true
+}
+
+impl ApplyConfig {
+    /// 读取配置文件，图片的相对路径转换为相对于配置文件所在目录
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("read {}: {}", path.display(), err))?;
+        let mut config: Self =
+            toml::from_str(&text).map_err(|err| format!("parse {}: {}", path.display(), err))?;
+        if config.image.is_relative()
+            && let Some(dir) = path.parent()
+        {
+            config.image = dir.join(&config.image);
+        }
+        Ok(config)
+    }
+}
+
+/// 应用结果，`--json` 时直接输出
+#[derive(Serialize)]
+pub struct ApplyReport {
+    /// JSON格式版本
+    pub schema_version: u32,
+    /// 工具版本
+    pub tool_version: &'static str,
+    /// `changed`、`unchanged` 或 `failed`
+    pub result: &'static str,
+    /// 是否只检查不写入
+    pub check: bool,
+    /// 与期望状态不一致的地方，`check` 时为将要修改的内容
+    pub changes: Vec<String>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+impl ApplyReport {
+    /// 未执行就失败的报告（如没有权限、配置无法读取）
+    pub fn failed(check: bool, error: String) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            result: "failed",
+            check,
+            changes: Vec::new(),
+            error: Some(error),
+        }
+    }
+
+    /// 输出格式化的JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("apply report serializes to JSON")
+    }
+}
+
+/// 将系统调整到配置描述的状态（需要管理员/root权限）
+///
+/// # 参数
+/// * `config` - 期望状态
+/// * `check` - 只比较，不写入
+pub fn apply(config: &ApplyConfig, check: bool) -> ApplyReport {
+    let mut changes = Vec::new();
+    let error = run(config, check, &mut changes).err();
+    let result = match (&error, changes.is_empty()) {
+        (Some(_), _) => "failed",
+        (None, true) => "unchanged",
+        (None, false) => "changed",
+    };
+    ApplyReport {
+        schema_version: SCHEMA_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION"),
+        result,
+        check,
+        changes,
+        error,
+    }
+}
+
+fn run(config: &ApplyConfig, check: bool, changes: &mut Vec<String>) -> Result<(), String> {
+    let mut platform_info = PlatformInfo::default();
+    platform_info
+        .get_info()
+        .map_err(|diag| format!("device not supported: {}", diag))?;
+
+    // 准备要写入的图片数据
+    let ext = config
+        .image
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .ok_or("image has no file extension")?;
+    let ext = if ext == "jpeg" {
+        "jpg".to_string()
+    } else {
+        ext
+    };
+    if !platform_info.support.contains(&ext.as_str()) {
+        return Err(format!("format {} is not supported by the firmware", ext));
+    }
+    let data = std::fs::read(&config.image)
+        .map_err(|err| format!("read {}: {}", config.image.display(), err))?;
+    let data = imaging::prepare(
+        data,
+        &ext,
+        config.resize,
+        platform_info.width,
+        platform_info.height,
+    )?;
+    let data = if config.sanitize {
+        imaging::sanitize(data, &ext)?
+    } else {
+        imaging::normalize(data, &ext)?
+    };
+    let (ext, data) = profiles::apply_quirks(data, &ext)?;
+    let expected_dvc = platform_info
+        .checksum_var(&data)
+        .ok_or_else(|| format!("unsupported version: 0x{:x}", platform_info.version))?;
+
+    // 与当前状态比较
+    let file_name = platform_info.logo_file_name(&ext);
+    if platform_info.enable == 0 {
+        changes.push("LBLDESP: enable custom logo".to_string());
+    }
+    if platform_info.lbldvc_var != expected_dvc {
+        changes.push("LBLDVC: update checksum".to_string());
+    }
+    let files = read_logo_files().ok_or("cannot read the ESP")?;
+    match files.iter().find(|(name, _)| *name == file_name) {
+        Some((_, current)) if *current == data => {}
+        Some(_) => changes.push(format!("ESP: replace {}", file_name)),
+        None => changes.push(format!("ESP: write {}", file_name)),
+    }
+    let stale: Vec<&str> = files
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| *name != file_name)
+        .collect();
+    if !stale.is_empty() {
+        changes.push(format!("ESP: remove {}", stale.join(", ")));
+    }
+    let logo_changed = !changes.is_empty();
+
+    let loading_icon = match config.loading_icon {
+        Some(_) if !NativePlatform::loading_icon_supported() => {
+            warn!("loading_icon is ignored, not supported on this platform");
+            None
+        }
+        Some(show) if NativePlatform::get_loading_icon() != show => {
+            changes.push(format!(
+                "loading icon: {}",
+                if show { "show" } else { "hide" }
+            ));
+            Some(show)
+        }
+        _ => None,
+    };
+
+    if check || changes.is_empty() {
+        return Ok(());
+    }
+
+    // 写入
+    if logo_changed {
+        info!("applying {}", config.image.display());
+        if !platform_info.set_logo_bytes(&data, &ext) {
+            return Err("set logo failed".to_string());
+        }
+        if let Err(err) = state::record(&data, &ext, &platform_info) {
+            warn!("record applied state failed: {}", err);
+        }
+        let mut updated_info = PlatformInfo::default();
+        updated_info
+            .get_info()
+            .map_err(|diag| format!("re-read variables failed: {}", diag))?;
+        match updated_info.verify_logo() {
+            VerifyStatus::Ok { file } => info!("verified {}", file),
+            status => return Err(format!("verification failed: {}", status)),
+        }
+    }
+    if let Some(show) = loading_icon
+        && !NativePlatform::set_loading_icon(show)
+    {
+        return Err("set loading icon failed".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! ACPI BGRT模块
+//!
+//! 固件在BGRT表中记录了启动时实际显示的Logo图片和位置，
+//! 可以用来查看上次开机显示的是什么，或在修改前导出出厂Logo
+
+use std::path::Path;
+
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+use crate::platform::{NativePlatform, PlatformOps};
+
+/// 固件上次启动时显示的Logo
+pub struct Bgrt {
+    /// 原始BMP数据
+    pub bmp: Vec<u8>,
+    /// 解码后的图片
+    pub image: RgbaImage,
+    /// 图片左上角在屏幕上的横坐标
+    pub xoffset: u32,
+    /// 图片左上角在屏幕上的纵坐标
+    pub yoffset: u32,
+    /// BGRT状态字段
+    pub status: u32,
+}
+
+impl Bgrt {
+    /// 读取并解码BGRT图片（不需要管理员/root权限）
+    pub fn read() -> Result<Self, String> {
+        let raw = NativePlatform::read_bgrt()?;
+        Self::parse(raw.image, raw.xoffset, raw.yoffset, raw.status)
+    }
+
+    /// 解码BGRT中的BMP图片
+    pub fn parse(bmp: Vec<u8>, xoffset: u32, yoffset: u32, status: u32) -> Result<Self, String> {
+        if !bmp.starts_with(b"BM") {
+            return Err("BGRT image is not a BMP file".to_string());
+        }
+        let image = image::load_from_memory_with_format(&bmp, ImageFormat::Bmp)
+            .map_err(|err| format!("decode BGRT image failed: {}", err))?
+            .to_rgba8();
+        Ok(Self {
+            bmp,
+            image,
+            xoffset,
+            yoffset,
+            status,
+        })
+    }
+
+    /// 图片宽高
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+
+    /// 状态字段bit 0：图片是否仍显示在屏幕上（操作系统接管屏幕后通常为0）
+    pub fn displayed(&self) -> bool {
+        self.status & 0x1 != 0
+    }
+
+    /// 状态字段bit 1-2：显示时的顺时针旋转角度
+    pub fn orientation(&self) -> u32 {
+        ((self.status >> 1) & 0x3) * 90
+    }
+
+    /// 导出图片，扩展名为bmp时保存原始数据，其他扩展名转换格式
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let is_bmp = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("bmp"));
+        let result = if is_bmp {
+            std::fs::write(path, &self.bmp).map_err(|err| err.to_string())
+        } else {
+            // BGRT图片没有透明通道，转换为RGB以便保存为JPEG等格式
+            DynamicImage::ImageRgba8(self.image.clone())
+                .to_rgb8()
+                .save(path)
+                .map_err(|err| err.to_string())
+        };
+        result.map_err(|err| format!("export to {} failed: {}", path.display(), err))
+    }
+}
@@ -0,0 +1,666 @@
+// 命令行工具，同时承载pkexec启动的特权辅助进程
+
+use std::sync::mpsc;
+
+use lenovo_logo_changer::apply::{self, ApplyConfig, ApplyReport};
+use lenovo_logo_changer::bgrt::Bgrt;
+use lenovo_logo_changer::confirm::{self, Confirmation};
+use lenovo_logo_changer::edk2_bmp;
+use lenovo_logo_changer::imaging;
+use lenovo_logo_changer::installed::InstalledLogo;
+use lenovo_logo_changer::lenlogo::LOGO_DIR;
+use lenovo_logo_changer::platform::{
+    NativePlatform, PlatformOps, ServiceOps, TaskSpec, TaskTrigger,
+};
+use lenovo_logo_changer::profiles;
+use lenovo_logo_changer::progress::{self, StepStatus};
+use lenovo_logo_changer::report::CompatReport;
+use lenovo_logo_changer::rotation::{self, RotationConfig};
+use lenovo_logo_changer::state::{self, CheckOutcome};
+use lenovo_logo_changer::status::StatusReport;
+use lenovo_logo_changer::{PlatformInfo, VerifyStatus, doctor, helper, operations};
+
+const USAGE: &str = "Usage: lenovo-logo-changer-cli <command>
+
+Commands:
+  info [--json]     Show the firmware's logo settings
+  set <IMAGE> [--no-sanitize]
+                    Change the boot logo; the image is decoded and re-encoded
+                    from scratch unless --no-sanitize is given
+  restore           Restore the default boot logo
+  verify [--json]   Check the installed logo against LBLDVC
+  status [--json]   Show settings, installed logo files and verification
+  apply <CONFIG> [--check] [--json]
+                    Bring the logo to the state described in CONFIG,
+                    writing only what differs (--check: report only)
+  check             Re-apply the last logo if a BIOS update reset it
+  install-service   Run `check` at every boot (systemd unit / scheduled task)
+  uninstall-service Remove the boot-time check
+  rotate <CONFIG> [--check]
+                    Pick the next logo from the library in CONFIG and set it
+  install-rotation <CONFIG> [--on shutdown|boot|daily]
+                    Rotate automatically (default: before every shutdown)
+  uninstall-rotation
+                    Remove the automatic rotation
+  convert <IMAGE> <OUTPUT>
+                    Convert IMAGE to the format given by OUTPUT's extension
+                    (jpg, png, bmp, gif, tga or pcx)
+  flatten <GIF> <OUTPUT> [--frame N]
+                    Save frame N (default 0) of an animated GIF as a
+                    single-frame GIF, transparent areas become black
+  check-bmp <BMP>   Check a BMP against the rules of EDK2's BMP decoder, which
+                    many Lenovo firmwares are based on, and explain each problem
+  confirm           Check whether the last boot displayed the custom logo
+  installed [FILE]  List the logo files on the ESP (read-only), optionally
+                    export the one the firmware uses to FILE
+  bgrt [FILE]       Show the logo displayed on the last boot (ACPI BGRT),
+                    optionally export it to FILE (.bmp keeps the original)
+  doctor [IMAGE]    Run read-only environment checks
+  report [--json]   Print a compatibility report (Markdown) to paste into
+                    the device reports issue; contains no serial numbers
+
+--json prints a versioned JSON report (see docs/json-output.md).";
+
+fn main() {
+    let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format_timestamp(None)
+        .format_target(false)
+        .try_init();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let json = args.iter().any(|arg| arg == "--json");
+    let check = args.iter().any(|arg| arg == "--check");
+    let sanitize = !args.iter().any(|arg| arg == "--no-sanitize");
+    args.retain(|arg| arg != "--json" && arg != "--check" && arg != "--no-sanitize");
+    let on = match args.iter().position(|arg| arg == "--on") {
+        Some(pos) if pos + 1 < args.len() => Some(args.drain(pos..pos + 2).nth(1).unwrap()),
+        Some(_) => std::process::exit(usage_error("--on requires a value")),
+        None => None,
+    };
+    let frame = match args.iter().position(|arg| arg == "--frame") {
+        Some(pos) if pos + 1 < args.len() => match args.drain(pos..pos + 2).nth(1).unwrap().parse()
+        {
+            Ok(frame) => frame,
+            Err(_) => std::process::exit(usage_error("--frame requires a frame number")),
+        },
+        Some(_) => std::process::exit(usage_error("--frame requires a value")),
+        None => 0,
+    };
+
+    let code = match args.first().map(String::as_str) {
+        Some(helper::HELPER_ARG) => helper::serve(),
+        Some("doctor") => {
+            let report = doctor::run(args.get(1).map(String::as_str));
+            println!("{}", report);
+            if report.has_failures() { 1 } else { 0 }
+        }
+        Some("info") => cmd_info(json),
+        Some("set") => match args.get(1) {
+            Some(image) => cmd_set(image, sanitize),
+            None => usage_error("set requires an image path"),
+        },
+        Some("restore") => cmd_restore(),
+        Some("verify") => cmd_verify(json),
+        Some("status") => cmd_status(json),
+        Some("apply") => match args.get(1) {
+            Some(config) => cmd_apply(config, check, json),
+            None => usage_error("apply requires a config file"),
+        },
+        Some("check") => cmd_check(),
+        Some("install-service") => cmd_install_service(),
+        Some("uninstall-service") => cmd_uninstall_task(CHECK_TASK, "boot-time check"),
+        Some("rotate") => match args.get(1) {
+            Some(config) => cmd_rotate(config, check),
+            None => usage_error("rotate requires a config file"),
+        },
+        Some("install-rotation") => match args.get(1) {
+            Some(config) => cmd_install_rotation(config, on.as_deref()),
+            None => usage_error("install-rotation requires a config file"),
+        },
+        Some("uninstall-rotation") => cmd_uninstall_task(ROTATION_TASK, "automatic rotation"),
+        Some("convert") => match (args.get(1), args.get(2)) {
+            (Some(image), Some(output)) => cmd_convert(image, output),
+            _ => usage_error("convert requires an image and an output path"),
+        },
+        Some("flatten") => match (args.get(1), args.get(2)) {
+            (Some(gif), Some(output)) => cmd_flatten(gif, output, frame),
+            _ => usage_error("flatten requires a gif and an output path"),
+        },
+        Some("check-bmp") => match args.get(1) {
+            Some(bmp) => cmd_check_bmp(bmp),
+            None => usage_error("check-bmp requires a bmp path"),
+        },
+        Some("confirm") => cmd_confirm(),
+        Some("bgrt") => cmd_bgrt(args.get(1).map(String::as_str)),
+        Some("report") => cmd_report(json),
+        Some("installed") => cmd_installed(args.get(1).map(String::as_str)),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            0
+        }
+        Some(command) => usage_error(&format!("Unknown command: {}", command)),
+        None => usage_error("Missing command"),
+    };
+    std::process::exit(code);
+}
+
+fn usage_error(message: &str) -> i32 {
+    eprintln!("{}\n\n{}", message, USAGE);
+    2
+}
+
+/// 读取平台信息，不支持时打印原因
+fn read_info() -> Option<PlatformInfo> {
+    let mut platform_info = PlatformInfo::default();
+    match platform_info.get_info() {
+        Ok(()) => Some(platform_info),
+        Err(diag) => {
+            eprintln!("Device not supported: {}", diag);
+            None
+        }
+    }
+}
+
+const ADMIN_REQUIRED: &str = "This command must be run as Administrator/root";
+
+/// 修改操作需要管理员/root权限
+fn require_admin() -> bool {
+    if NativePlatform::is_admin() {
+        true
+    } else {
+        eprintln!("{}", ADMIN_REQUIRED);
+        false
+    }
+}
+
+fn cmd_info(json: bool) -> i32 {
+    if json {
+        let (report, info) = StatusReport::collect();
+        println!("{}", report.to_json());
+        return if info.is_some() { 0 } else { 1 };
+    }
+
+    let Some(info) = read_info() else {
+        return 1;
+    };
+    print_info(&info);
+    0
+}
+
+fn print_info(info: &PlatformInfo) {
+    match profiles::current() {
+        Some(profile) => {
+            println!("Device           : known good ({})", profile.name);
+            for difference in profile.differences(info) {
+                println!(
+                    "                   differs from the database: {}",
+                    difference
+                );
+            }
+        }
+        None => println!("Device           : untested, please report it in issue #34"),
+    }
+    println!("Enabled          : {}", info.enable != 0);
+    println!("Max image size   : {}x{}", info.width, info.height);
+    println!("Supported formats: {}", info.support.join(" / "));
+    println!("Version          : 0x{:x}", info.version);
+    println!("LBLDESP          : {}", hex::encode(info.lbldesp_var));
+    println!("LBLDVC           : {}", hex::encode(info.lbldvc_var));
+}
+
+fn cmd_set(image: &str, sanitize: bool) -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    let Some(info) = read_info() else {
+        return 1;
+    };
+    let show_loading_icon = NativePlatform::get_loading_icon();
+    let image = image.to_string();
+    let (sender, receiver) = mpsc::channel();
+    let worker = std::thread::spawn(move || {
+        progress::with_sender(sender, || {
+            operations::set_logo(&image, sanitize, show_loading_icon, info, false)
+        })
+    });
+    for event in receiver {
+        match event.status {
+            StepStatus::Running => {}
+            StepStatus::Done(elapsed) => {
+                println!("[ok]     {} ({} ms)", event.step, elapsed.as_millis())
+            }
+            StepStatus::Failed(elapsed) => {
+                println!("[failed] {} ({} ms)", event.step, elapsed.as_millis())
+            }
+        }
+    }
+    match worker.join() {
+        Ok(result) if result.success => 0,
+        _ => 1,
+    }
+}
+
+fn cmd_restore() -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    let Some(info) = read_info() else {
+        return 1;
+    };
+    let result = operations::restore_logo(info, false);
+    if result.success { 0 } else { 1 }
+}
+
+fn cmd_verify(json: bool) -> i32 {
+    if json {
+        let (mut report, info) = StatusReport::collect();
+        // 没有权限时也输出JSON，在 `error` 中说明
+        if !NativePlatform::is_admin() {
+            report.error = Some(ADMIN_REQUIRED.to_string());
+            println!("{}", report.to_json());
+            return 1;
+        }
+        let Some(info) = info else {
+            println!("{}", report.to_json());
+            return 1;
+        };
+        let status = info.verify_logo();
+        report.set_verify(&status);
+        println!("{}", report.to_json());
+        return if matches!(status, VerifyStatus::Ok { .. }) {
+            0
+        } else {
+            1
+        };
+    }
+
+    if !require_admin() {
+        return 1;
+    }
+    let Some(info) = read_info() else {
+        return 1;
+    };
+    let status = info.verify_logo();
+    println!("{}", status);
+    if matches!(status, VerifyStatus::Ok { .. }) {
+        0
+    } else {
+        1
+    }
+}
+
+/// 汇总设置、ESP中的Logo文件和校验结果，没有权限时只输出设置
+fn cmd_status(json: bool) -> i32 {
+    let (mut report, info) = StatusReport::collect();
+    let Some(info) = info else {
+        if json {
+            println!("{}", report.to_json());
+        } else if let Some(diag) = &report.diagnosis {
+            eprintln!("Device not supported: {}", diag.message);
+        }
+        return 1;
+    };
+
+    let is_admin = NativePlatform::is_admin();
+    if is_admin {
+        report.read_esp(&info);
+    }
+    report.set_confirmation(&confirm::confirm());
+    if json {
+        println!("{}", report.to_json());
+        return 0;
+    }
+
+    print_info(&info);
+    if let Some(confirmation) = &report.boot_confirmation {
+        println!("Last boot        : {}", confirmation.message);
+    }
+    if !is_admin {
+        println!("\nRun as Administrator/root to inspect the installed logo.");
+        return 0;
+    }
+    println!();
+    match &report.installed_logo {
+        Some(files) if files.is_empty() => println!("Installed logo   : none"),
+        Some(files) => {
+            for file in files {
+                println!("Installed logo   : {} ({} bytes)", file.path, file.size);
+                println!("  SHA-256        : {}", file.sha256);
+            }
+        }
+        None => println!("Installed logo   : cannot read the ESP"),
+    }
+    if let Some(verify) = &report.verify {
+        println!("Verify           : {}", verify.message);
+    }
+    0
+}
+
+fn cmd_apply(config_path: &str, check: bool, json: bool) -> i32 {
+    let report = if !NativePlatform::is_admin() {
+        ApplyReport::failed(check, ADMIN_REQUIRED.to_string())
+    } else {
+        match ApplyConfig::load(std::path::Path::new(config_path)) {
+            Ok(config) => apply::apply(&config, check),
+            Err(err) => ApplyReport::failed(check, err),
+        }
+    };
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report.result);
+        for change in &report.changes {
+            println!("  - {}", change);
+        }
+        if let Some(err) = &report.error {
+            eprintln!("{}", err);
+        }
+    }
+    if report.error.is_some() { 1 } else { 0 }
+}
+
+fn cmd_check() -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    match state::check() {
+        Ok(CheckOutcome::NothingRecorded) => {
+            println!("No applied logo recorded, nothing to check");
+            0
+        }
+        Ok(CheckOutcome::Intact) => {
+            println!("Logo settings intact");
+            0
+        }
+        Ok(CheckOutcome::Reapplied { reasons }) => {
+            println!("Firmware reset the logo settings, re-applied:");
+            for reason in reasons {
+                println!("  - {}", reason);
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("Check failed: {}", err);
+            1
+        }
+    }
+}
+
+/// 开机检查任务的名称
+const CHECK_TASK: &str = "check";
+
+/// 自动轮换任务的名称
+const ROTATION_TASK: &str = "rotate";
+
+fn cmd_install_service() -> i32 {
+    install_task(
+        CHECK_TASK,
+        "Re-apply the custom Lenovo boot logo after a BIOS reset",
+        vec!["check".to_string()],
+        TaskTrigger::Boot,
+    )
+}
+
+/// 以当前程序安装后台任务
+fn install_task(name: &str, description: &str, args: Vec<String>, trigger: TaskTrigger) -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => {
+            eprintln!("Cannot locate executable: {}", err);
+            return 1;
+        }
+    };
+    let task = TaskSpec {
+        name,
+        description,
+        exe: &exe,
+        args: &args,
+        trigger,
+    };
+    match NativePlatform::install_task(&task) {
+        Ok(()) => {
+            println!("Installed {}", description);
+            0
+        }
+        Err(err) => {
+            eprintln!("Install failed: {}", err);
+            1
+        }
+    }
+}
+
+fn cmd_uninstall_task(name: &str, description: &str) -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    match NativePlatform::uninstall_task(name) {
+        Ok(()) => {
+            println!("Removed {}", description);
+            0
+        }
+        Err(err) => {
+            eprintln!("Uninstall failed: {}", err);
+            1
+        }
+    }
+}
+
+fn cmd_rotate(config_path: &str, check: bool) -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    let result = RotationConfig::load(std::path::Path::new(config_path))
+        .and_then(|config| rotation::rotate(&config, check));
+    match result {
+        Ok(rotation::RotationResult {
+            image: Some(image),
+            report: Some(report),
+        }) => {
+            println!("{}: {}", image, report.result);
+            for change in &report.changes {
+                println!("  - {}", change);
+            }
+            if let Some(err) = &report.error {
+                eprintln!("{}", err);
+                return 1;
+            }
+            0
+        }
+        Ok(_) => {
+            println!("No rule matches today, keeping the current logo");
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn cmd_install_rotation(config_path: &str, on: Option<&str>) -> i32 {
+    let trigger = match on.unwrap_or("shutdown") {
+        "shutdown" => TaskTrigger::Shutdown,
+        "boot" => TaskTrigger::Boot,
+        "daily" => TaskTrigger::Daily,
+        other => return usage_error(&format!("Unknown --on value: {}", other)),
+    };
+    // 先检查配置，任务中使用绝对路径
+    let config_path = match std::fs::canonicalize(config_path) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("{}: {}", config_path, err);
+            return 1;
+        }
+    };
+    if let Err(err) = RotationConfig::load(&config_path) {
+        eprintln!("{}", err);
+        return 1;
+    }
+    install_task(
+        ROTATION_TASK,
+        "Rotate the Lenovo boot logo",
+        vec!["rotate".to_string(), config_path.display().to_string()],
+        trigger,
+    )
+}
+
+fn cmd_bgrt(export: Option<&str>) -> i32 {
+    let bgrt = match Bgrt::read() {
+        Ok(bgrt) => bgrt,
+        Err(err) => {
+            eprintln!("BGRT not available: {}", err);
+            return 1;
+        }
+    };
+    let (width, height) = bgrt.dimensions();
+    println!(
+        "Image            : {}x{} ({} bytes BMP)",
+        width,
+        height,
+        bgrt.bmp.len()
+    );
+    println!("Position         : {}, {}", bgrt.xoffset, bgrt.yoffset);
+    println!("Orientation      : {} degrees", bgrt.orientation());
+    println!("Still displayed  : {}", bgrt.displayed());
+
+    if let Some(path) = export {
+        if let Err(err) = bgrt.export(std::path::Path::new(path)) {
+            eprintln!("{}", err);
+            return 1;
+        }
+        println!("Exported to {}", path);
+    }
+    0
+}
+
+fn cmd_installed(export: Option<&str>) -> i32 {
+    if !require_admin() {
+        return 1;
+    }
+    let Some(info) = read_info() else {
+        return 1;
+    };
+    let logos = match InstalledLogo::read_all(&info) {
+        Ok(logos) => logos,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    if logos.is_empty() {
+        println!("No logo files in {}", LOGO_DIR);
+    }
+    for logo in &logos {
+        println!(
+            "{}  {} bytes  sha256 {}  {}",
+            logo.path(),
+            logo.data.len(),
+            logo.sha256,
+            if logo.matches_lbldvc {
+                "matches LBLDVC"
+            } else {
+                "does not match LBLDVC"
+            }
+        );
+    }
+
+    if let Some(path) = export {
+        let Some(logo) = InstalledLogo::active(&logos) else {
+            eprintln!("No installed logo to export");
+            return 1;
+        };
+        if let Err(err) = logo.export(std::path::Path::new(path)) {
+            eprintln!("{}", err);
+            return 1;
+        }
+        println!("Exported {} to {}", logo.name, path);
+    }
+    0
+}
+
+fn cmd_report(json: bool) -> i32 {
+    let report = CompatReport::collect(None);
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.to_markdown());
+    }
+    0
+}
+
+fn cmd_convert(image: &str, output: &str) -> i32 {
+    let ext_of = |path: &str| {
+        std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    let result = std::fs::read(image)
+        .map_err(|err| format!("read {}: {}", image, err))
+        .and_then(|data| imaging::convert(&data, &ext_of(image), &ext_of(output)))
+        .and_then(|data| {
+            std::fs::write(output, data).map_err(|err| format!("write {}: {}", output, err))
+        });
+    match result {
+        Ok(()) => {
+            println!("Converted to {}", output);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn cmd_check_bmp(bmp: &str) -> i32 {
+    let data = match std::fs::read(bmp) {
+        Ok(data) => data,
+        Err(err) => {
+            eprintln!("read {}: {}", bmp, err);
+            return 1;
+        }
+    };
+    let violations = edk2_bmp::check(&data);
+    if violations.is_empty() {
+        println!("{}: accepted by EDK2's BMP decoder", bmp);
+        return 0;
+    }
+    println!("{}: rejected by EDK2's BMP decoder", bmp);
+    for violation in &violations {
+        println!("  - {}", violation);
+    }
+    1
+}
+
+fn cmd_flatten(gif: &str, output: &str, frame: usize) -> i32 {
+    let result = std::fs::read(gif)
+        .map_err(|err| format!("read {}: {}", gif, err))
+        .and_then(|data| imaging::flatten_gif(&data, frame))
+        .and_then(|data| {
+            std::fs::write(output, data).map_err(|err| format!("write {}: {}", output, err))
+        });
+    match result {
+        Ok(()) => {
+            println!("Saved frame {} to {}", frame, output);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn cmd_confirm() -> i32 {
+    let confirmation = confirm::confirm();
+    println!("{}", confirmation);
+    match confirmation {
+        Confirmation::Rejected { .. } | Confirmation::Unknown(_) => 1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,292 @@
+//! 开机确认模块
+//!
+//! 设置Logo并重启后，用户很难分辨固件是显示了自定义Logo还是回退到了联想Logo。
+//! 这里比较BGRT中固件实际显示的图片和记录的已安装Logo：
+//! 两张图都先去掉纯色边框（固件可能把Logo放在整屏画布中），
+//! 再缩小到相同尺寸比较灰度，从而忽略缩放和摆放位置的差异。
+//! 只比较非背景的像素，避免大面积黑色背景让不同的Logo也显得相似。
+
+use std::fmt;
+
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, Pixel, RgbImage, RgbaImage};
+
+use crate::bgrt::Bgrt;
+use crate::encoders;
+use crate::imaging;
+use crate::platform::{NativePlatform, PlatformOps};
+use crate::state;
+
+/// 相似度达到该值时认为固件显示的是自定义Logo
+pub const MATCH_THRESHOLD: f32 = 0.88;
+
+/// 比较时缩小到的边长
+const COMPARE_SIZE: u32 = 32;
+
+/// 去边框时与边框颜色的最大差异（RGB三通道差值之和）
+const BORDER_TOLERANCE: u32 = 48;
+
+/// 灰度与背景相差超过该值的像素视为Logo内容，只比较这些像素
+const FOREGROUND_TOLERANCE: u8 = 24;
+
+/// 宽高比相差超过该比例时直接认为不是同一张图
+const ASPECT_TOLERANCE: f32 = 0.15;
+
+/// 开机确认结果
+#[derive(Clone, Debug)]
+pub enum Confirmation {
+    /// 没有记录已安装的Logo
+    NothingRecorded,
+    /// 设置Logo之后还没有重启
+    NotRebooted,
+    /// 上次开机显示的是自定义Logo
+    Confirmed {
+        /// 相似度（0~1）
+        similarity: f32,
+    },
+    /// 上次开机显示的不是自定义Logo，固件可能拒绝了图片
+    Rejected {
+        /// 相似度（0~1）
+        similarity: f32,
+    },
+    /// 无法判断（BGRT不可用、图片无法解码等）
+    Unknown(String),
+}
+
+impl Confirmation {
+    /// 稳定的结果代码，用于JSON输出
+    pub fn code(&self) -> &'static str {
+        match self {
+            Confirmation::NothingRecorded => "nothing_recorded",
+            Confirmation::NotRebooted => "not_rebooted",
+            Confirmation::Confirmed { .. } => "confirmed",
+            Confirmation::Rejected { .. } => "rejected",
+            Confirmation::Unknown(_) => "unknown",
+        }
+    }
+
+    /// 相似度，只有比较过时才有值
+    pub fn similarity(&self) -> Option<f32> {
+        match self {
+            Confirmation::Confirmed { similarity } | Confirmation::Rejected { similarity } => {
+                Some(*similarity)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Confirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Confirmation::NothingRecorded => write!(f, "no custom logo recorded"),
+            Confirmation::NotRebooted => write!(f, "reboot to confirm the custom logo"),
+            Confirmation::Confirmed { similarity } => write!(
+                f,
+                "custom logo confirmed displayed (similarity {:.0}%)",
+                similarity * 100.0
+            ),
+            Confirmation::Rejected { similarity } => write!(
+                f,
+                "firmware rejected it, the last boot showed a different image (similarity {:.0}%)",
+                similarity * 100.0
+            ),
+            Confirmation::Unknown(reason) => write!(f, "cannot confirm: {}", reason),
+        }
+    }
+}
+
+/// 检查上次开机时固件是否显示了记录的自定义Logo（不需要管理员/root权限）
+pub fn confirm() -> Confirmation {
+    let (applied, installed) = match state::load() {
+        Ok(Some(recorded)) => recorded,
+        Ok(None) => return Confirmation::NothingRecorded,
+        Err(err) => return Confirmation::Unknown(err),
+    };
+
+    // BGRT反映的是本次开机，设置时间晚于开机时间说明还没重启
+    match NativePlatform::boot_time() {
+        Some(boot_time) if applied.applied_at >= boot_time => return Confirmation::NotRebooted,
+        Some(_) => {}
+        None => return Confirmation::Unknown("cannot determine the boot time".to_string()),
+    }
+
+    let bgrt = match Bgrt::read() {
+        Ok(bgrt) => bgrt,
+        Err(err) => return Confirmation::Unknown(err),
+    };
+    let installed = match imaging::decode(&installed, &applied.ext) {
+        Ok(img) => img,
+        Err(err) => return Confirmation::Unknown(err),
+    };
+
+    let similarity = compare(&bgrt.image, &installed);
+    if similarity >= MATCH_THRESHOLD {
+        Confirmation::Confirmed { similarity }
+    } else {
+        Confirmation::Rejected { similarity }
+    }
+}
+
+/// 比较固件显示的图片和已安装的Logo，返回0~1的相似度
+///
+/// 只比较两张图中至少一张不是背景的像素，大片相同的背景（如黑色）不会抬高相似度
+pub fn compare(displayed: &RgbaImage, installed: &DynamicImage) -> f32 {
+    // 固件绘制时透明部分一般显示为黑色
+    let displayed = encoders::flatten_on_black(&DynamicImage::ImageRgba8(displayed.clone()));
+    let installed = encoders::flatten_on_black(installed);
+    let (displayed_bg, installed_bg) = (background_luma(&displayed), background_luma(&installed));
+    let displayed = trim_border(&displayed);
+    let installed = trim_border(&installed);
+
+    let aspect = |img: &RgbImage| img.width() as f32 / img.height() as f32;
+    let (a, b) = (aspect(&displayed), aspect(&installed));
+    if (a - b).abs() / a.max(b) > ASPECT_TOLERANCE {
+        return 0.0;
+    }
+
+    let small = |img: &RgbImage| -> GrayImage {
+        let resized = imageops::resize(img, COMPARE_SIZE, COMPARE_SIZE, FilterType::Triangle);
+        DynamicImage::ImageRgb8(resized).to_luma8()
+    };
+    let (displayed, installed) = (small(&displayed), small(&installed));
+    let is_foreground = |luma: u8, bg: u8| luma.abs_diff(bg) > FOREGROUND_TOLERANCE;
+    let (total, count) = displayed
+        .pixels()
+        .zip(installed.pixels())
+        .filter(|(p, q)| is_foreground(p.0[0], displayed_bg) || is_foreground(q.0[0], installed_bg))
+        .fold((0u64, 0u64), |(total, count), (p, q)| {
+            (total + p.0[0].abs_diff(q.0[0]) as u64, count + 1)
+        });
+    if count == 0 {
+        // 两张图都是纯色，只比较颜色
+        return 1.0 - displayed_bg.abs_diff(installed_bg) as f32 / 255.0;
+    }
+    let mean = total as f32 / count as f32;
+    1.0 - mean / 255.0
+}
+
+/// 背景（左上角像素）的灰度
+fn background_luma(img: &RgbImage) -> u8 {
+    img.get_pixel_checked(0, 0)
+        .map_or(0, |pixel| pixel.to_luma().0[0])
+}
+
+/// 去掉与左上角颜色相同的边框
+fn trim_border(img: &RgbImage) -> RgbImage {
+    if img.width() == 0 || img.height() == 0 {
+        return img.clone();
+    }
+    let border = img.get_pixel(0, 0).0;
+    let differs = |x: u32, y: u32| {
+        let p = img.get_pixel(x, y).0;
+        (0..3).map(|i| p[i].abs_diff(border[i]) as u32).sum::<u32>() > BORDER_TOLERANCE
+    };
+
+    let (mut left, mut top, mut right, mut bottom) = (img.width(), img.height(), 0, 0);
+    for y in 0..img.height() {
+        for x in 0..img.width() {
+            if differs(x, y) {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x);
+                bottom = bottom.max(y);
+            }
+        }
+    }
+    if left > right || top > bottom {
+        // 整张图都是同一种颜色
+        return img.clone();
+    }
+    imageops::crop_imm(img, left, top, right - left + 1, bottom - top + 1).to_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    /// 黑色画布上按 `(x, y, w, h)` 画白色矩形
+    fn canvas(width: u32, height: u32, rects: &[(u32, u32, u32, u32)]) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(width, height, BLACK);
+        for &(x0, y0, w, h) in rects {
+            for y in y0..y0 + h {
+                for x in x0..x0 + w {
+                    img.put_pixel(x, y, WHITE);
+                }
+            }
+        }
+        img
+    }
+
+    /// 靠近四角各一个小方块，固定内容的边界，中间再放一个方块
+    fn sparse_logo(x: u32, y: u32) -> RgbaImage {
+        canvas(
+            100,
+            100,
+            &[
+                (2, 2, 4, 4),
+                (94, 2, 4, 4),
+                (2, 94, 4, 4),
+                (94, 94, 4, 4),
+                (x, y, 20, 20),
+            ],
+        )
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let logo = canvas(200, 100, &[(20, 20, 60, 20), (120, 40, 40, 40)]);
+        let similarity = compare(&logo, &DynamicImage::ImageRgba8(logo.clone()));
+        assert!(similarity > 0.99, "similarity {}", similarity);
+    }
+
+    #[test]
+    fn shifted_in_larger_canvas_matches() {
+        // 固件把Logo放在整屏画布中的其他位置
+        let installed = canvas(200, 100, &[(10, 10, 60, 20), (110, 30, 40, 40)]);
+        let displayed = canvas(800, 600, &[(300, 250, 60, 20), (400, 270, 40, 40)]);
+        let similarity = compare(&displayed, &DynamicImage::ImageRgba8(installed));
+        assert!(similarity >= MATCH_THRESHOLD, "similarity {}", similarity);
+    }
+
+    #[test]
+    fn scaled_image_matches() {
+        let installed = canvas(
+            200,
+            100,
+            &[(10, 10, 60, 20), (100, 20, 40, 40), (150, 70, 40, 20)],
+        );
+        let displayed = imageops::resize(&installed, 300, 150, FilterType::Triangle);
+        let similarity = compare(&displayed, &DynamicImage::ImageRgba8(installed));
+        assert!(similarity >= MATCH_THRESHOLD, "similarity {}", similarity);
+    }
+
+    #[test]
+    fn mostly_black_different_logos_do_not_match() {
+        // 内容边界相同，只有很小的区域不同，按全图平均会显得非常相似
+        let installed = sparse_logo(10, 10);
+        let displayed = sparse_logo(70, 70);
+        let similarity = compare(&displayed, &DynamicImage::ImageRgba8(installed));
+        assert!(similarity < MATCH_THRESHOLD, "similarity {}", similarity);
+    }
+
+    #[test]
+    fn different_aspect_ratio_does_not_match() {
+        let installed = canvas(200, 100, &[(0, 0, 200, 100)]);
+        let displayed = canvas(200, 100, &[(50, 0, 100, 100)]);
+        let similarity = compare(&displayed, &DynamicImage::ImageRgba8(installed));
+        assert_eq!(similarity, 0.0);
+    }
+
+    #[test]
+    fn blank_logo_against_black_screen() {
+        let black = canvas(100, 100, &[]);
+        let white = RgbaImage::from_pixel(100, 100, WHITE);
+        assert!(compare(&black, &DynamicImage::ImageRgba8(black.clone())) > 0.99);
+        assert!(compare(&black, &DynamicImage::ImageRgba8(white)) < MATCH_THRESHOLD);
+    }
+}
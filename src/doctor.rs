@@ -0,0 +1,304 @@
+//! 环境诊断模块
+//!
+//! 依次执行只读的预检查，输出 pass/warn/fail 报告和处理建议
+
+use std::fmt;
+
+use crate::i18n::{Lang, t};
+use crate::lenlogo::{PlatformInfo, SupportDiagnosis};
+use crate::operations;
+use crate::platform::{DiagnosticOps, NativePlatform, PlatformOps};
+
+/// 检查结果等级
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// 检查通过
+    Pass,
+    /// 可能影响使用，但不一定导致失败
+    Warn,
+    /// 会导致设置Logo失败
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Warn => write!(f, "WARN"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+/// 单项检查结果
+pub struct CheckResult {
+    /// 检查项名称
+    pub name: &'static str,
+    /// 检查结果等级
+    pub status: CheckStatus,
+    /// 检查到的具体情况
+    pub detail: String,
+    /// 处理建议，通过时一般为空
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// 诊断报告
+#[derive(Default)]
+pub struct DoctorReport {
+    /// 按执行顺序排列的检查结果
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    /// 是否有检查失败
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Fail)
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "[{}] {}: {}", check.status, check.name, check.detail)?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "       hint: {}", hint)?;
+            }
+        }
+        write!(
+            f,
+            "\n{} passed, {} warnings, {} failed",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail)
+        )
+    }
+}
+
+/// 执行所有检查
+///
+/// # 参数
+/// * `image_path` - 计划使用的图片，按重新编码后的大小检查ESP剩余空间
+pub fn run(image_path: Option<&str>) -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    report.checks.push(check_privileges());
+    report.checks.push(check_efivars());
+    if let Some(check) = check_immutable_flag() {
+        report.checks.push(check);
+    }
+    report.checks.extend(check_esp(image_path));
+
+    let mut platform_info = PlatformInfo::default();
+    let support = platform_info.get_info();
+    report.checks.push(check_variables(&support));
+    report
+        .checks
+        .push(check_checksum_scheme(&platform_info, &support));
+
+    report
+}
+
+fn check_privileges() -> CheckResult {
+    const NAME: &str = "Privileges";
+    if NativePlatform::is_admin() {
+        CheckResult::pass(NAME, "running with Administrator/root privileges")
+    } else {
+        // 诊断本身只读，没有权限不妨碍使用，只在修改时需要提权
+        CheckResult::warn(
+            NAME,
+            "running without Administrator/root privileges",
+            "changing or restoring the logo requires elevation; run as Administrator or with sudo",
+        )
+    }
+}
+
+fn check_efivars() -> CheckResult {
+    const NAME: &str = "EFI variables";
+    if !NativePlatform::is_uefi_boot() {
+        return CheckResult::fail(
+            NAME,
+            "system is not booted in UEFI mode",
+            t(Lang::En, "diag_not_uefi_hint"),
+        );
+    }
+    match NativePlatform::efivars_mount_writable() {
+        Some(true) => CheckResult::pass(NAME, "EFI variable interface available and writable"),
+        Some(false) => CheckResult::fail(
+            NAME,
+            "efivarfs is mounted read-only",
+            "remount it writable: mount -o remount,rw /sys/firmware/efi/efivars",
+        ),
+        None => CheckResult::fail(
+            NAME,
+            "efivarfs is not mounted",
+            t(Lang::En, "diag_efivarfs_hint"),
+        ),
+    }
+}
+
+fn check_immutable_flag() -> Option<CheckResult> {
+    const NAME: &str = "Immutable flag";
+    NativePlatform::immutable_flag_support().map(|result| match result {
+        Ok(()) => CheckResult::pass(NAME, "FS_IOC_GETFLAGS/SETFLAGS supported on efivarfs"),
+        Err(err) => CheckResult::fail(
+            NAME,
+            err,
+            "the kernel must support the immutable flag on efivarfs (Linux 4.6+) to unlock variables for writing",
+        ),
+    })
+}
+
+fn check_esp(image_path: Option<&str>) -> Vec<CheckResult> {
+    const NAME: &str = "ESP";
+    const SPACE: &str = "ESP free space";
+
+    let esp = match NativePlatform::esp_info() {
+        Ok(esp) => esp,
+        Err(err) => {
+            return vec![CheckResult::fail(
+                NAME,
+                err,
+                "make sure the disk has an EFI System Partition and that you run with elevated privileges",
+            )];
+        }
+    };
+
+    let mut checks = Vec::new();
+    let detail = format!("{} ({})", esp.device, esp.fs_type);
+    let fs_upper = esp.fs_type.to_uppercase();
+    if fs_upper.contains("FAT") {
+        checks.push(CheckResult::pass(NAME, detail));
+    } else {
+        checks.push(CheckResult::warn(
+            NAME,
+            detail,
+            "the ESP is expected to be FAT12/16/32; firmware may not read other filesystems",
+        ));
+    }
+
+    // 按写入时的实际大小计算：与 `set` 默认一样重新编码后的数据
+    let image_size = match image_path.map(|path| operations::prepare_image(path, true)) {
+        Some(Ok((_, data))) => Some(data.len() as u64),
+        Some(Err(err)) => {
+            checks.push(CheckResult::fail(
+                SPACE,
+                format!("cannot prepare image: {}", err),
+                "check the image path and format",
+            ));
+            return checks;
+        }
+        None => None,
+    };
+
+    match (esp.free_bytes, image_size) {
+        (Some(free), Some(size)) if free < size => checks.push(CheckResult::fail(
+            SPACE,
+            format!("{} bytes free, image needs {} bytes", free, size),
+            "free up space on the ESP or use a smaller image",
+        )),
+        (Some(free), Some(size)) => checks.push(CheckResult::pass(
+            SPACE,
+            format!("{} bytes free, image needs {} bytes", free, size),
+        )),
+        (Some(free), None) => checks.push(CheckResult::pass(SPACE, format!("{} bytes free", free))),
+        (None, _) => checks.push(CheckResult::warn(
+            SPACE,
+            "free space unknown (ESP is not mounted)",
+            "mount the ESP (e.g. at /boot/efi) to let the check measure free space",
+        )),
+    }
+    checks
+}
+
+fn check_variables(support: &Result<(), SupportDiagnosis>) -> CheckResult {
+    const NAME: &str = "LBLDESP/LBLDVC";
+    match support {
+        // 格式和版本问题留给后续检查报告
+        Ok(())
+        | Err(SupportDiagnosis::ZeroFormatMask)
+        | Err(SupportDiagnosis::UnknownVersion(_)) => {
+            CheckResult::pass(NAME, "both variables present with the expected layout")
+        }
+        Err(diag) => CheckResult::fail(
+            NAME,
+            diag.to_string(),
+            diag.fill(&t(Lang::En, &format!("{}_hint", diag.i18n_key()))),
+        ),
+    }
+}
+
+fn check_checksum_scheme(
+    platform_info: &PlatformInfo,
+    support: &Result<(), SupportDiagnosis>,
+) -> CheckResult {
+    const NAME: &str = "Checksum scheme";
+    match support {
+        Ok(())
+        | Err(SupportDiagnosis::ZeroFormatMask)
+        | Err(SupportDiagnosis::UnknownVersion(_)) => {}
+        Err(_) => {
+            return CheckResult::warn(
+                NAME,
+                "skipped, variables could not be read",
+                "fix the LBLDESP/LBLDVC check first",
+            );
+        }
+    }
+
+    if let Err(diag @ SupportDiagnosis::ZeroFormatMask) = support {
+        return CheckResult::fail(NAME, diag.to_string(), t(Lang::En, "diag_zero_format_hint"));
+    }
+
+    let formats = platform_info.support.join("/");
+    match platform_info.version {
+        0x20003 => CheckResult::pass(
+            NAME,
+            format!("version 0x20003 (SHA-256), formats: {}", formats),
+        ),
+        0x20000 => CheckResult::pass(
+            NAME,
+            format!(
+                "version 0x20000 (CRC32 of first 512 bytes), formats: {}",
+                formats
+            ),
+        ),
+        version => CheckResult::fail(
+            NAME,
+            format!("unknown version 0x{:x}", version),
+            t(Lang::En, "diag_unknown_version_hint"),
+        ),
+    }
+}
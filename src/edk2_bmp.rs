@@ -0,0 +1,449 @@
+//! EDK2 BMP检查模块
+//!
+//! 许多联想固件基于EDK2，其 `TranslateBmpToGopBlt`（BmpSupportLib）只接受很窄的一类BMP。
+//! 这里按相同的规则检查BMP文件并说明每一处不符合的原因，
+//! 作为"固件会不会接受这张图"模拟检查的第一步。
+//!
+//! EDK2的规则：
+//! * 文件以 `BM` 开头，文件头中的文件大小等于实际大小
+//! * 信息头为40字节的 `BITMAPINFOHEADER`
+//! * 不压缩（`BI_RGB`），每像素1、4、8、24或32位
+//! * 宽高不为0，高度为正（自下而上存放），各项大小计算不溢出
+//! * 图片数据偏移不小于文件头大小；偏移大于文件头时，1/4/8位图片的调色板（2/16/256项）
+//!   必须完整位于文件头和像素数据之间（偏移正好等于文件头大小时EDK2不检查调色板）
+//! * 每行填充到4字节的倍数，像素数据正好占满图片数据偏移之后的全部内容
+
+use std::fmt;
+
+/// `BMP_IMAGE_HEADER`（文件头和信息头）的大小
+const HEADER_SIZE: u32 = 54;
+
+/// EDK2接受的信息头大小（`BITMAPINFOHEADER`）
+const INFO_HEADER_SIZE: u32 = 40;
+
+/// EDK2支持的每像素位数
+const SUPPORTED_BITS: [u16; 5] = [1, 4, 8, 24, 32];
+
+/// 不符合EDK2规则的地方
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BmpViolation {
+    /// 文件比BMP文件头还短
+    TooShort {
+        /// 文件大小
+        len: usize,
+    },
+    /// 文件不以 `BM` 开头
+    NotBmp,
+    /// 文件头中的文件大小与实际大小不同
+    SizeMismatch {
+        /// 文件头中的大小
+        header: u32,
+        /// 实际大小
+        actual: usize,
+    },
+    /// 信息头不是40字节的 `BITMAPINFOHEADER`
+    UnsupportedInfoHeader {
+        /// 信息头大小
+        size: u32,
+    },
+    /// 使用了压缩
+    Compressed {
+        /// 压缩方式
+        compression: u32,
+    },
+    /// 不支持的每像素位数
+    UnsupportedBitDepth {
+        /// 每像素位数
+        bits: u16,
+    },
+    /// 宽或高为0
+    ZeroDimension,
+    /// 高度为负，像素自上而下存放
+    TopDown {
+        /// 文件中的高度
+        height: i32,
+    },
+    /// 宽高太大，计算缓冲区大小时溢出
+    Overflow {
+        /// 宽度
+        width: u32,
+        /// 高度
+        height: u32,
+    },
+    /// 图片数据偏移位于文件头之内或文件之外
+    InvalidOffset {
+        /// 图片数据偏移
+        offset: u32,
+    },
+    /// 调色板放不下
+    PaletteTooSmall {
+        /// 需要的调色板项数
+        entries: u32,
+        /// 文件头和像素数据之间的空间（字节）
+        room: u32,
+    },
+    /// 像素数据大小与宽高计算的大小不同
+    DataSizeMismatch {
+        /// 每行大小（含填充）
+        line: u64,
+        /// 按宽高计算的大小
+        expected: u64,
+        /// 图片数据偏移之后的实际大小
+        actual: u64,
+    },
+}
+
+impl fmt::Display for BmpViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BmpViolation::TooShort { len } => write!(
+                f,
+                "file is {} bytes, shorter than the {}-byte BMP header",
+                len, HEADER_SIZE
+            ),
+            BmpViolation::NotBmp => write!(f, "file does not start with \"BM\""),
+            BmpViolation::SizeMismatch { header, actual } => write!(
+                f,
+                "header says the file is {} bytes but it is {} bytes; \
+                 EDK2 requires them to be equal, so trailing data is rejected",
+                header, actual
+            ),
+            BmpViolation::UnsupportedInfoHeader { size } => write!(
+                f,
+                "info header is {} bytes; EDK2 only reads the {}-byte BITMAPINFOHEADER \
+                 (save without color space information)",
+                size, INFO_HEADER_SIZE
+            ),
+            BmpViolation::Compressed { compression } => write!(
+                f,
+                "compression type {} is used; EDK2 only accepts uncompressed (BI_RGB) bitmaps",
+                compression
+            ),
+            BmpViolation::UnsupportedBitDepth { bits } => write!(
+                f,
+                "{} bits per pixel; EDK2 supports 1, 4, 8, 24 and 32",
+                bits
+            ),
+            BmpViolation::ZeroDimension => write!(f, "width or height is 0"),
+            BmpViolation::TopDown { height } => write!(
+                f,
+                "height is {} (top-down rows); EDK2 reads the height as unsigned \
+                 and only supports bottom-up bitmaps",
+                height
+            ),
+            BmpViolation::Overflow { width, height } => write!(
+                f,
+                "{}x{} is too large, computing the buffer size overflows",
+                width, height
+            ),
+            BmpViolation::InvalidOffset { offset } => write!(
+                f,
+                "pixel data offset {} is inside the {}-byte header or past the end of the file",
+                offset, HEADER_SIZE
+            ),
+            BmpViolation::PaletteTooSmall { entries, room } => write!(
+                f,
+                "a {}-entry palette needs {} bytes between the header and the pixel data, \
+                 but there are only {}; EDK2 always reads the full palette",
+                entries,
+                entries * 4,
+                room
+            ),
+            BmpViolation::DataSizeMismatch {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "pixel data is {} bytes but should be {} ({} bytes per row, \
+                 padded to a multiple of 4); EDK2 requires an exact match",
+                actual, expected, line
+            ),
+        }
+    }
+}
+
+/// 按EDK2的规则检查BMP，返回所有不符合的地方，为空表示EDK2会接受
+///
+/// 文件头损坏到无法继续时只返回已发现的问题
+pub fn check(data: &[u8]) -> Vec<BmpViolation> {
+    let mut violations = Vec::new();
+    if data.len() < HEADER_SIZE as usize {
+        violations.push(BmpViolation::TooShort { len: data.len() });
+        return violations;
+    }
+    if !data.starts_with(b"BM") {
+        violations.push(BmpViolation::NotBmp);
+        return violations;
+    }
+
+    let u16_at = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let size = u32_at(2);
+    let offset = u32_at(10);
+    let info_size = u32_at(14);
+    let width = u32_at(18);
+    let height = u32_at(22);
+    let bits = u16_at(28);
+    let compression = u32_at(30);
+
+    if size as usize != data.len() {
+        violations.push(BmpViolation::SizeMismatch {
+            header: size,
+            actual: data.len(),
+        });
+    }
+    if info_size != INFO_HEADER_SIZE {
+        violations.push(BmpViolation::UnsupportedInfoHeader { size: info_size });
+    }
+    if compression != 0 {
+        violations.push(BmpViolation::Compressed { compression });
+    }
+    if !SUPPORTED_BITS.contains(&bits) {
+        violations.push(BmpViolation::UnsupportedBitDepth { bits });
+    }
+    if width == 0 || height == 0 {
+        violations.push(BmpViolation::ZeroDimension);
+    }
+    if (height as i32) < 0 {
+        violations.push(BmpViolation::TopDown {
+            height: height as i32,
+        });
+    }
+
+    // 调色板位于文件头和像素数据之间，与EDK2相同，只在两者之间有空间时检查
+    if offset < HEADER_SIZE || offset as usize > data.len() {
+        violations.push(BmpViolation::InvalidOffset { offset });
+    } else if offset > HEADER_SIZE {
+        let entries = match bits {
+            1 => 2,
+            4 => 16,
+            8 => 256,
+            _ => 0,
+        };
+        let room = offset - HEADER_SIZE;
+        if room < entries * 4 {
+            violations.push(BmpViolation::PaletteTooSmall { entries, room });
+        }
+    }
+
+    // 与EDK2相同，用32位计算每行和总大小并检查溢出
+    if SUPPORTED_BITS.contains(&bits) && width != 0 && height != 0 && (height as i32) > 0 {
+        let sizes = width
+            .checked_mul(bits as u32)
+            .and_then(|row_bits| row_bits.checked_add(31))
+            .map(|row_bits| (row_bits >> 3) & !0x3)
+            .and_then(|line| Some((line, line.checked_mul(height)?)))
+            .filter(|_| {
+                // GOP缓冲区每像素4字节
+                width
+                    .checked_mul(height)
+                    .and_then(|pixels| pixels.checked_mul(4))
+                    .is_some()
+            });
+        match sizes {
+            Some((line, expected)) => {
+                let actual = (data.len() as u64).saturating_sub(offset as u64);
+                if offset as usize <= data.len() && actual != expected as u64 {
+                    violations.push(BmpViolation::DataSizeMismatch {
+                        line: line as u64,
+                        expected: expected as u64,
+                        actual,
+                    });
+                }
+            }
+            None => violations.push(BmpViolation::Overflow { width, height }),
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造BMP：`palette` 为调色板项数，`pixels` 为像素数据字节数
+    fn bmp(width: i32, height: i32, bits: u16, palette: u32, pixels: usize) -> Vec<u8> {
+        let offset = HEADER_SIZE + palette * 4;
+        let size = offset as usize + pixels;
+        let mut data = vec![0u8; size];
+        data[0..2].copy_from_slice(b"BM");
+        data[2..6].copy_from_slice(&(size as u32).to_le_bytes());
+        data[10..14].copy_from_slice(&offset.to_le_bytes());
+        data[14..18].copy_from_slice(&INFO_HEADER_SIZE.to_le_bytes());
+        data[18..22].copy_from_slice(&width.to_le_bytes());
+        data[22..26].copy_from_slice(&height.to_le_bytes());
+        data[26..28].copy_from_slice(&1u16.to_le_bytes());
+        data[28..30].copy_from_slice(&bits.to_le_bytes());
+        data
+    }
+
+    fn set_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn valid_24_bit_is_accepted() {
+        // 3像素一行9字节，填充到12字节
+        assert_eq!(check(&bmp(3, 2, 24, 0, 24)), vec![]);
+    }
+
+    #[test]
+    fn valid_8_bit_is_accepted() {
+        assert_eq!(check(&bmp(3, 2, 8, 256, 8)), vec![]);
+    }
+
+    #[test]
+    fn valid_1_4_32_bit_are_accepted() {
+        assert_eq!(check(&bmp(9, 2, 1, 2, 8)), vec![]);
+        assert_eq!(check(&bmp(9, 2, 4, 16, 16)), vec![]);
+        assert_eq!(check(&bmp(3, 2, 32, 0, 24)), vec![]);
+    }
+
+    #[test]
+    fn palette_is_not_checked_without_room() {
+        // 与EDK2相同，偏移等于文件头大小时不检查调色板
+        assert_eq!(check(&bmp(3, 2, 8, 0, 8)), vec![]);
+    }
+
+    #[test]
+    fn short_palette_is_rejected() {
+        assert_eq!(
+            check(&bmp(3, 2, 8, 16, 8)),
+            vec![BmpViolation::PaletteTooSmall {
+                entries: 256,
+                room: 64
+            }]
+        );
+    }
+
+    #[test]
+    fn too_short_is_rejected() {
+        assert_eq!(check(b"BM"), vec![BmpViolation::TooShort { len: 2 }]);
+    }
+
+    #[test]
+    fn bad_magic_is_rejected() {
+        let mut data = bmp(3, 2, 24, 0, 24);
+        data[0..2].copy_from_slice(b"PN");
+        assert_eq!(check(&data), vec![BmpViolation::NotBmp]);
+    }
+
+    #[test]
+    fn unsupported_bit_depth_is_rejected() {
+        assert_eq!(
+            check(&bmp(3, 2, 16, 0, 16)),
+            vec![BmpViolation::UnsupportedBitDepth { bits: 16 }]
+        );
+    }
+
+    #[test]
+    fn compression_is_rejected() {
+        let mut data = bmp(3, 2, 8, 256, 8);
+        set_u32(&mut data, 30, 1); // BI_RLE8
+        assert_eq!(
+            check(&data),
+            vec![BmpViolation::Compressed { compression: 1 }]
+        );
+    }
+
+    #[test]
+    fn unsupported_info_header_is_rejected() {
+        let mut data = bmp(3, 2, 24, 0, 24);
+        set_u32(&mut data, 14, 124); // BITMAPV5HEADER
+        assert_eq!(
+            check(&data),
+            vec![BmpViolation::UnsupportedInfoHeader { size: 124 }]
+        );
+    }
+
+    #[test]
+    fn top_down_rows_are_rejected() {
+        assert_eq!(
+            check(&bmp(3, -2, 24, 0, 24)),
+            vec![BmpViolation::TopDown { height: -2 }]
+        );
+    }
+
+    #[test]
+    fn zero_dimension_is_rejected() {
+        assert_eq!(
+            check(&bmp(0, 2, 24, 0, 0)),
+            vec![BmpViolation::ZeroDimension]
+        );
+    }
+
+    #[test]
+    fn bad_offset_is_rejected() {
+        let mut data = bmp(3, 2, 24, 0, 24);
+        set_u32(&mut data, 10, 14);
+        assert!(check(&data).contains(&BmpViolation::InvalidOffset { offset: 14 }));
+
+        set_u32(&mut data, 10, 1000);
+        assert!(check(&data).contains(&BmpViolation::InvalidOffset { offset: 1000 }));
+    }
+
+    #[test]
+    fn overflow_is_rejected() {
+        assert_eq!(
+            check(&bmp(0x4000_0000, 0x4000_0000, 32, 0, 0)),
+            vec![BmpViolation::Overflow {
+                width: 0x4000_0000,
+                height: 0x4000_0000
+            }]
+        );
+    }
+
+    #[test]
+    fn truncated_pixel_data_is_rejected() {
+        // 每行没有填充到4字节：9字节一行，共18字节
+        assert_eq!(
+            check(&bmp(3, 2, 24, 0, 18)),
+            vec![BmpViolation::DataSizeMismatch {
+                line: 12,
+                expected: 24,
+                actual: 18
+            }]
+        );
+    }
+
+    #[test]
+    fn trailing_data_is_rejected() {
+        let mut data = bmp(3, 2, 24, 0, 24);
+        data.extend_from_slice(b"junk");
+        assert_eq!(
+            check(&data),
+            vec![
+                BmpViolation::SizeMismatch {
+                    header: 78,
+                    actual: 82
+                },
+                BmpViolation::DataSizeMismatch {
+                    line: 12,
+                    expected: 24,
+                    actual: 28
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_sample_is_accepted() {
+        // 样例是只带部分调色板的8位BMP，重新编码后符合EDK2的规则
+        let path = format!("{}/example_img/bmp1.bmp", env!("CARGO_MANIFEST_DIR"));
+        let data = std::fs::read(path).unwrap();
+        assert!(matches!(
+            check(&data)[..],
+            [BmpViolation::PaletteTooSmall { entries: 256, .. }]
+        ));
+        let normalized = crate::imaging::normalize(data, "bmp").unwrap();
+        assert_eq!(check(&normalized), vec![]);
+    }
+}
@@ -0,0 +1,336 @@
+//! 固件格式编码模块
+//!
+//! 固件可能只支持TGA、PCX、GIF中的一部分，image库不能写PCX，写TGA时默认使用RLE压缩，
+//! 因此这里实现这些格式的保守写法，使普通PNG/JPEG可以转换为固件声明支持的任意格式：
+//! * TGA：24位、不压缩、自下而上
+//! * PCX：版本5，8位调色板（1个平面）或24位（3个平面），按扫描线RLE编码
+//! * GIF：单帧，不含循环扩展，颜色超过256种时量化为调色板
+//!
+//! 透明通道一律叠加到黑色背景上（固件在黑色背景上显示Logo）。
+
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+
+use color_quant::NeuQuant;
+use image::codecs::gif::GifEncoder;
+use image::{DynamicImage, ExtendedColorType, RgbImage};
+
+/// GIF和8位PCX量化时的采样系数，1最精确，30最快
+const QUANTIZE_SPEED: i32 = 10;
+
+/// PCX每个游程最多重复的次数
+const PCX_MAX_RUN: usize = 63;
+
+/// PCX的颜色深度
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcxDepth {
+    /// 8位，文件末尾附带256色调色板
+    Indexed8,
+    /// 24位，每行依次存放R、G、B三个平面
+    Rgb24,
+}
+
+/// 去掉透明通道，半透明像素按透明度叠加到黑色上
+pub fn flatten_on_black(img: &DynamicImage) -> RgbImage {
+    if !img.color().has_alpha() {
+        return img.to_rgb8();
+    }
+    let rgba = img.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+/// 编码为24位、不压缩、自下而上的TGA
+pub fn write_tga(img: &DynamicImage) -> Result<Vec<u8>, String> {
+    let rgb = flatten_on_black(img);
+    let (width, height) = (dimension_u16(rgb.width())?, dimension_u16(rgb.height())?);
+
+    let mut out = Vec::with_capacity(18 + rgb.as_raw().len());
+    // ID长度、无调色板、类型2（不压缩真彩色）、调色板说明（5字节）
+    out.extend_from_slice(&[0, 0, 2, 0, 0, 0, 0, 0]);
+    // 原点（0, 0）、宽、高
+    out.extend_from_slice(&[0, 0, 0, 0]);
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    // 24位，描述字节为0：原点在左下角，没有Alpha位
+    out.extend_from_slice(&[24, 0]);
+    for row in rgb.rows().rev() {
+        for pixel in row {
+            let [r, g, b] = pixel.0;
+            out.extend_from_slice(&[b, g, r]);
+        }
+    }
+    Ok(out)
+}
+
+/// 编码为PCX
+pub fn write_pcx(img: &DynamicImage, depth: PcxDepth) -> Result<Vec<u8>, String> {
+    let rgb = flatten_on_black(img);
+    let (width, height) = (rgb.width(), rgb.height());
+    let (xmax, ymax) = (dimension_u16(width)? - 1, dimension_u16(height)? - 1);
+    // 每个平面每行的字节数必须为偶数
+    let bytes_per_line = (width as usize).div_ceil(2) * 2;
+    let planes: u8 = match depth {
+        PcxDepth::Indexed8 => 1,
+        PcxDepth::Rgb24 => 3,
+    };
+
+    let mut out = vec![0u8; 128];
+    out[0] = 0x0A; // 制造商
+    out[1] = 5; // 版本5：PC Paintbrush 3.0及以后，支持256色调色板和24位
+    out[2] = 1; // RLE编码
+    out[3] = 8; // 每个平面每像素8位
+    out[8..10].copy_from_slice(&xmax.to_le_bytes());
+    out[10..12].copy_from_slice(&ymax.to_le_bytes());
+    out[12..14].copy_from_slice(&72u16.to_le_bytes());
+    out[14..16].copy_from_slice(&72u16.to_le_bytes());
+    out[65] = planes;
+    out[66..68].copy_from_slice(&(bytes_per_line as u16).to_le_bytes());
+    out[68..70].copy_from_slice(&1u16.to_le_bytes()); // 彩色调色板
+
+    let mut line = vec![0u8; bytes_per_line];
+    match depth {
+        PcxDepth::Indexed8 => {
+            let (palette, indices) = quantize(&rgb);
+            for row in indices.chunks(width as usize) {
+                line[..row.len()].copy_from_slice(row);
+                pcx_rle(&line, &mut out);
+            }
+            // 调色板标记和256色调色板
+            out.push(0x0C);
+            out.extend_from_slice(&palette);
+            out.resize(out.len() + 768 - palette.len(), 0);
+        }
+        PcxDepth::Rgb24 => {
+            for row in rgb.rows() {
+                let row: Vec<[u8; 3]> = row.map(|pixel| pixel.0).collect();
+                for plane in 0..3 {
+                    for (byte, pixel) in line.iter_mut().zip(&row) {
+                        *byte = pixel[plane];
+                    }
+                    pcx_rle(&line, &mut out);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 按PCX的RLE规则编码一行：重复的字节或不小于0xC0的字节写为 `0xC0|次数` 加字节
+fn pcx_rle(line: &[u8], out: &mut Vec<u8>) {
+    let mut pos = 0;
+    while pos < line.len() {
+        let byte = line[pos];
+        let run = line[pos..]
+            .iter()
+            .take(PCX_MAX_RUN)
+            .take_while(|&&next| next == byte)
+            .count();
+        if run > 1 || byte >= 0xC0 {
+            out.push(0xC0 | run as u8);
+        }
+        out.push(byte);
+        pos += run;
+    }
+}
+
+/// 图片的颜色种数，超过 `limit` 时停止计数并返回 `limit + 1`
+pub fn color_count(img: &DynamicImage, limit: usize) -> usize {
+    let mut colors = HashSet::new();
+    for pixel in flatten_on_black(img).pixels() {
+        colors.insert(pixel.0);
+        if colors.len() > limit {
+            break;
+        }
+    }
+    colors.len()
+}
+
+/// 生成最多256色的调色板（RGB，每色3字节）和每个像素的索引，颜色不超过256种时不损失颜色
+fn quantize(rgb: &RgbImage) -> (Vec<u8>, Vec<u8>) {
+    let mut exact: HashMap<[u8; 3], u8> = HashMap::new();
+    for pixel in rgb.pixels() {
+        if exact.len() > 256 {
+            break;
+        }
+        let next = exact.len();
+        exact.entry(pixel.0).or_insert(next as u8);
+    }
+    if exact.len() <= 256 {
+        let mut palette = vec![0u8; exact.len() * 3];
+        for (color, &index) in &exact {
+            palette[index as usize * 3..index as usize * 3 + 3].copy_from_slice(color);
+        }
+        let indices = rgb.pixels().map(|pixel| exact[&pixel.0]).collect();
+        return (palette, indices);
+    }
+
+    let rgba: Vec<u8> = rgb
+        .pixels()
+        .flat_map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2], 255])
+        .collect();
+    let quant = NeuQuant::new(QUANTIZE_SPEED, 256, &rgba);
+    let indices = rgba
+        .chunks_exact(4)
+        .map(|pixel| quant.index_of(pixel) as u8)
+        .collect();
+    (quant.color_map_rgb(), indices)
+}
+
+/// 编码为单帧GIF，颜色超过256种时量化
+pub fn write_gif(img: &DynamicImage) -> Result<Vec<u8>, String> {
+    let rgb = flatten_on_black(img);
+    let mut out = Cursor::new(Vec::new());
+    GifEncoder::new_with_speed(&mut out, QUANTIZE_SPEED)
+        .encode(
+            rgb.as_raw(),
+            rgb.width(),
+            rgb.height(),
+            ExtendedColorType::Rgb8,
+        )
+        .map_err(|err| format!("encode gif failed: {}", err))?;
+    Ok(out.into_inner())
+}
+
+/// TGA和PCX的宽高字段为16位
+fn dimension_u16(value: u32) -> Result<u16, String> {
+    u16::try_from(value)
+        .ok()
+        .filter(|&value| value > 0)
+        .ok_or_else(|| format!("image dimension {} out of range", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::imaging;
+
+    const SAMPLES: [&str; 6] = [
+        "bmp1.bmp", "gif1.gif", "jpg1.jpg", "pcx1.pcx", "png1.png", "tga1.tga",
+    ];
+
+    /// 量化为256色时允许的每通道平均误差
+    const PALETTE_TOLERANCE: f64 = 8.0;
+
+    fn samples() -> Vec<(&'static str, DynamicImage)> {
+        SAMPLES
+            .iter()
+            .map(|name| {
+                let path = format!("{}/example_img/{}", env!("CARGO_MANIFEST_DIR"), name);
+                let data = std::fs::read(&path).expect("read sample");
+                let ext = name.rsplit('.').next().unwrap();
+                (*name, imaging::decode(&data, ext).expect("decode sample"))
+            })
+            .collect()
+    }
+
+    /// 每通道的平均误差，尺寸不同时失败
+    fn mean_error(name: &str, expected: &RgbImage, actual: &DynamicImage) -> f64 {
+        let actual = actual.to_rgb8();
+        assert_eq!(
+            expected.dimensions(),
+            actual.dimensions(),
+            "{}: dimensions differ",
+            name
+        );
+        let total: u64 = expected
+            .as_raw()
+            .iter()
+            .zip(actual.as_raw())
+            .map(|(a, b)| a.abs_diff(*b) as u64)
+            .sum();
+        total as f64 / expected.as_raw().len() as f64
+    }
+
+    #[test]
+    fn tga_round_trip_is_lossless() {
+        for (name, img) in samples() {
+            let encoded = write_tga(&img).unwrap();
+            // 类型2（不压缩），24位，原点在左下角
+            assert_eq!((encoded[2], encoded[16], encoded[17]), (2, 24, 0));
+            let decoded =
+                image::load_from_memory_with_format(&encoded, image::ImageFormat::Tga).unwrap();
+            assert_eq!(
+                mean_error(name, &flatten_on_black(&img), &decoded),
+                0.0,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn pcx_rgb24_round_trip_is_lossless() {
+        for (name, img) in samples() {
+            let encoded = write_pcx(&img, PcxDepth::Rgb24).unwrap();
+            assert_eq!((encoded[1], encoded[65]), (5, 3));
+            let decoded = imaging::decode(&encoded, "pcx").unwrap();
+            assert_eq!(
+                mean_error(name, &flatten_on_black(&img), &decoded),
+                0.0,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn pcx_indexed8_round_trip_within_palette_error() {
+        for (name, img) in samples() {
+            let encoded = write_pcx(&img, PcxDepth::Indexed8).unwrap();
+            assert_eq!((encoded[1], encoded[65]), (5, 1));
+            assert_eq!(
+                encoded[encoded.len() - 769],
+                0x0C,
+                "{}: palette marker",
+                name
+            );
+            let decoded = imaging::decode(&encoded, "pcx").unwrap();
+            let error = mean_error(name, &flatten_on_black(&img), &decoded);
+            if color_count(&img, 256) <= 256 {
+                assert_eq!(error, 0.0, "{}", name);
+            } else {
+                assert!(error <= PALETTE_TOLERANCE, "{}: mean error {}", name, error);
+            }
+        }
+    }
+
+    #[test]
+    fn gif_round_trip_within_palette_error() {
+        for (name, img) in samples() {
+            let encoded = write_gif(&img).unwrap();
+            assert_eq!(imaging::gif_frame_count(&encoded).unwrap(), 1, "{}", name);
+            let decoded =
+                image::load_from_memory_with_format(&encoded, image::ImageFormat::Gif).unwrap();
+            let error = mean_error(name, &flatten_on_black(&img), &decoded);
+            assert!(error <= PALETTE_TOLERANCE, "{}: mean error {}", name, error);
+        }
+    }
+
+    #[test]
+    fn bmp_and_jpg_conversion_flattens_alpha_on_black() {
+        // 半透明的白色，直接丢弃透明通道会得到白色
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            16,
+            16,
+            image::Rgba([255, 255, 255, 128]),
+        ));
+        let mut png = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+        let expected = flatten_on_black(&img);
+        assert_eq!(expected.get_pixel(0, 0).0, [128, 128, 128]);
+
+        let bmp = imaging::convert(&png, "png", "bmp").unwrap();
+        let decoded = imaging::decode(&bmp, "bmp").unwrap();
+        assert_eq!(mean_error("bmp", &expected, &decoded), 0.0);
+
+        let jpg = imaging::convert(&png, "png", "jpg").unwrap();
+        let decoded = imaging::decode(&jpg, "jpg").unwrap();
+        let error = mean_error("jpg", &expected, &decoded);
+        assert!(error <= PALETTE_TOLERANCE, "mean error {}", error);
+    }
+}
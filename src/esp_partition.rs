@@ -1,14 +1,20 @@
-// ESP分区操作模块
-// 使用平台抽象层来实现跨平台兼容
+//! ESP分区操作模块
+//!
+//! 使用平台抽象层来实现跨平台兼容
 
 use crate::platform::{EspPartitionOps, NativePlatform};
 
 /// 删除ESP分区中的Logo路径
-pub(crate) fn delete_logo_path() -> bool {
+pub fn delete_logo_path() -> bool {
     NativePlatform::delete_logo_path()
 }
 
-/// 复制文件到ESP分区
-pub(crate) fn copy_file_to_esp(src: &str, dst: &str) -> bool {
-    NativePlatform::copy_file_to_esp(src, dst)
+/// 将数据写入ESP分区中的文件
+pub fn write_file_to_esp(data: &[u8], dst: &str) -> bool {
+    NativePlatform::write_file_to_esp(data, dst)
+}
+
+/// 读取ESP分区Logo目录中的所有文件
+pub fn read_logo_files() -> Option<Vec<(String, Vec<u8>)>> {
+    NativePlatform::read_logo_files()
 }
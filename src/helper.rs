@@ -0,0 +1,437 @@
+//! 特权辅助进程模块
+//!
+//! 界面以普通用户运行，所有ESP和EFI变量的修改都交给通过pkexec启动的辅助进程完成
+//!
+//! 协议（基于辅助进程的stdin/stdout，每个请求一行，遇到EOF结束会话）：
+//!
+//! ```text
+//! GET_INFO                  -> OK <LBLDESP hex> <属性hex> <LBLDVC hex> <属性hex>
+//! SET_LOGO <扩展名> <长度>   -> OK        请求行之后紧跟<长度>字节的图片数据，写入前重新生成
+//! RESTORE                   -> OK
+//! VERIFY                    -> OK <结果>[\t<文件名>...]
+//! READ_LOGO                 -> OK <文件数>  响应行之后每个文件先发送一行"<长度> <文件名>"，
+//!                                         再发送<长度>字节的文件内容
+//! ```
+//!
+//! 失败时返回 `ERR <错误信息>`。执行 `SET_LOGO` 时，在响应行之前可能有多行
+//! `PROGRESS <事件>` 报告步骤进度，格式见 [`ProgressEvent::encode`]
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use efivar::efi::VariableFlags;
+use log::{error, info, warn};
+
+use crate::esp_partition::read_logo_files;
+use crate::imaging;
+use crate::lenlogo::{PlatformInfo, VerifyStatus};
+use crate::platform::{NativePlatform, PlatformOps};
+use crate::progress::{self, ProgressEvent};
+use crate::state;
+
+/// 启动辅助进程时使用的命令行参数
+pub const HELPER_ARG: &str = "--helper";
+
+/// 承载辅助进程的命令行工具，与界面程序安装在同一目录
+pub const HELPER_EXECUTABLE: &str = "lenovo-logo-changer-cli";
+
+/// 允许传输的最大图片大小
+const MAX_IMAGE_SIZE: usize = 32 * 1024 * 1024;
+
+/// 辅助进程入口，返回进程退出码
+pub fn serve() -> i32 {
+    if !NativePlatform::is_admin() {
+        eprintln!("The helper must be started through pkexec or as root");
+        return 1;
+    }
+
+    let mut reader = BufReader::new(io::stdin().lock());
+    let mut writer = io::stdout().lock();
+
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => return 0,
+            Ok(_) => {}
+            Err(err) => {
+                error!("helper: read request failed: {}", err);
+                return 1;
+            }
+        }
+
+        let mut body = Vec::new();
+        // 进度事件在执行过程中立即发送，响应行之前到达客户端
+        let result = progress::with_handler(
+            |event| {
+                let mut stdout = io::stdout().lock();
+                let _ =
+                    writeln!(stdout, "PROGRESS {}", event.encode()).and_then(|_| stdout.flush());
+            },
+            || handle_request(line.trim_end(), &mut reader, &mut body),
+        );
+        let response = match result {
+            Ok(payload) if payload.is_empty() => "OK".to_string(),
+            Ok(payload) => format!("OK {}", payload),
+            Err(err) => {
+                error!("helper: {}", err);
+                body.clear();
+                format!("ERR {}", err.replace('\n', " "))
+            }
+        };
+
+        if writeln!(writer, "{}", response)
+            .and_then(|_| writer.write_all(&body))
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            return 1;
+        }
+    }
+}
+
+/// 处理一个请求，返回响应行的内容，`body` 为响应行之后发送的数据
+fn handle_request(
+    line: &str,
+    reader: &mut impl Read,
+    body: &mut Vec<u8>,
+) -> Result<String, String> {
+    let mut parts = line.split(' ');
+    let command = parts.next().unwrap_or_default();
+    info!("helper: {}", command);
+
+    // 每个请求都在特权进程中重新读取变量，不信任客户端传来的状态
+    let mut platform_info = PlatformInfo::default();
+    let support = platform_info.get_info();
+
+    match command {
+        "GET_INFO" => {
+            support.map_err(|diag| diag.to_string())?;
+            Ok(format!(
+                "{} {:x} {} {:x}",
+                hex::encode(platform_info.lbldesp_var),
+                platform_info.lbldesp_attr.bits(),
+                hex::encode(platform_info.lbldvc_var),
+                platform_info.lbldvc_attr.bits()
+            ))
+        }
+        "SET_LOGO" => {
+            let ext = parts
+                .next()
+                .ok_or("missing extension")?
+                .to_ascii_lowercase();
+            let len: usize = parts
+                .next()
+                .and_then(|len| len.parse().ok())
+                .ok_or("missing or invalid length")?;
+            if len == 0 || len > MAX_IMAGE_SIZE {
+                return Err(format!("invalid image size: {} bytes", len));
+            }
+
+            // 先读完数据，保证出错时协议仍然同步
+            let mut data = vec![0u8; len];
+            reader
+                .read_exact(&mut data)
+                .map_err(|err| format!("read image data failed: {}", err))?;
+
+            support.map_err(|diag| diag.to_string())?;
+            let data = sanitize_image(&platform_info, &ext, data)?;
+
+            if platform_info.set_logo_bytes(&data, &ext) {
+                if let Err(err) = state::record(&data, &ext, &platform_info) {
+                    warn!("record applied state failed: {}", err);
+                }
+                Ok(String::new())
+            } else {
+                Err("set logo failed".to_string())
+            }
+        }
+        "RESTORE" => {
+            support.map_err(|diag| diag.to_string())?;
+            if platform_info.restore_logo() {
+                // 用户主动恢复默认Logo，开机检查不应再重新设置
+                if let Err(err) = state::clear() {
+                    warn!("clear applied state failed: {}", err);
+                }
+                Ok(String::new())
+            } else {
+                Err("restore logo failed".to_string())
+            }
+        }
+        "VERIFY" => {
+            support.map_err(|diag| diag.to_string())?;
+            Ok(encode_verify(&platform_info.verify_logo()))
+        }
+        "READ_LOGO" => {
+            let files = read_logo_files().ok_or("cannot read the ESP")?;
+            for (name, data) in &files {
+                // 文件名放在行尾，可以包含空格
+                writeln!(body, "{} {}", data.len(), name.replace('\n', " "))
+                    .map_err(|err| err.to_string())?;
+                body.extend_from_slice(data);
+            }
+            Ok(files.len().to_string())
+        }
+        _ => Err(format!("unknown command: {}", command)),
+    }
+}
+
+/// 检查图片并完全重新生成，返回写入ESP的数据
+///
+/// 不信任客户端是否已经清洗过：附加数据、元数据和辅助块都不会以root权限写入ESP
+fn sanitize_image(
+    platform_info: &PlatformInfo,
+    ext: &str,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    validate_image(platform_info, ext, &data)?;
+    imaging::sanitize(data, ext)
+}
+
+/// 检查扩展名是否为固件支持的格式，且文件头与扩展名一致
+fn validate_image(platform_info: &PlatformInfo, ext: &str, data: &[u8]) -> Result<(), String> {
+    let ext = if ext == "jpeg" { "jpg" } else { ext };
+    if !platform_info.support.contains(&ext) {
+        return Err(format!("format {} is not supported by the firmware", ext));
+    }
+
+    let valid = match ext {
+        "jpg" => data.starts_with(&[0xFF, 0xD8, 0xFF]),
+        "png" => data.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "bmp" => data.starts_with(b"BM"),
+        "gif" => data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a"),
+        // PCX: 制造商标识0x0A，编码方式1（RLE）
+        "pcx" => data.len() > 128 && data[0] == 0x0A && data[2] == 1,
+        // TGA没有魔数，检查颜色表类型和图像类型
+        "tga" => data.len() > 18 && data[1] <= 1 && matches!(data[2], 1 | 2 | 3 | 9 | 10 | 11),
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("image data is not a valid {} file", ext))
+    }
+}
+
+fn encode_verify(status: &VerifyStatus) -> String {
+    match status {
+        VerifyStatus::Ok { file } => format!("ok\t{}", file),
+        VerifyStatus::NotEnabled => "not_enabled".to_string(),
+        VerifyStatus::NoLogoFile => "no_logo_file".to_string(),
+        VerifyStatus::ChecksumMismatch { files } => format!("mismatch\t{}", files.join("\t")),
+        VerifyStatus::EspUnavailable => "esp_unavailable".to_string(),
+    }
+}
+
+fn decode_verify(payload: &str) -> Result<VerifyStatus, String> {
+    let mut parts = payload.split('\t');
+    match parts.next().unwrap_or_default() {
+        "ok" => Ok(VerifyStatus::Ok {
+            file: parts.next().unwrap_or_default().to_string(),
+        }),
+        "not_enabled" => Ok(VerifyStatus::NotEnabled),
+        "no_logo_file" => Ok(VerifyStatus::NoLogoFile),
+        "mismatch" => Ok(VerifyStatus::ChecksumMismatch {
+            files: parts.map(str::to_string).collect(),
+        }),
+        "esp_unavailable" => Ok(VerifyStatus::EspUnavailable),
+        other => Err(format!("unexpected verify result: {}", other)),
+    }
+}
+
+/// 检查是否可以通过pkexec提权启动辅助进程（仅Linux）
+pub fn can_elevate() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join("pkexec").is_file()))
+        .unwrap_or(false)
+}
+
+/// 特权辅助进程的客户端
+pub struct HelperClient {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl HelperClient {
+    /// 通过pkexec启动辅助进程，会弹出polkit认证对话框
+    pub fn spawn() -> Result<Self, String> {
+        let exe = std::env::current_exe()
+            .map_err(|err| format!("cannot locate executable: {}", err))?
+            .with_file_name(HELPER_EXECUTABLE);
+
+        let mut child = Command::new("pkexec")
+            .arg(exe)
+            .arg(HELPER_ARG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| format!("failed to start pkexec: {}", err))?;
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().ok_or("helper stdout unavailable")?;
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    fn request(&mut self, line: &str, body: Option<&[u8]>) -> Result<String, String> {
+        let stdin = self.stdin.as_mut().ok_or("helper stdin closed")?;
+        writeln!(stdin, "{}", line)
+            .and_then(|_| body.map_or(Ok(()), |body| stdin.write_all(body)))
+            .and_then(|_| stdin.flush())
+            .map_err(|err| format!("send request to helper failed: {}", err))?;
+
+        let mut response = String::new();
+        loop {
+            response.clear();
+            match self.stdout.read_line(&mut response) {
+                // pkexec认证被取消或辅助进程退出
+                Ok(0) => return Err("helper exited (authentication cancelled?)".to_string()),
+                Ok(_) => {}
+                Err(err) => return Err(format!("read helper response failed: {}", err)),
+            }
+            let response = response.trim_end();
+            // 转发响应行之前的进度事件
+            if let Some(event) = response.strip_prefix("PROGRESS ") {
+                match ProgressEvent::decode(event) {
+                    Some(event) => progress::emit(event),
+                    None => warn!("unexpected helper progress: {}", event),
+                }
+                continue;
+            }
+            return if let Some(err) = response.strip_prefix("ERR ") {
+                Err(err.to_string())
+            } else if response == "OK" {
+                Ok(String::new())
+            } else if let Some(payload) = response.strip_prefix("OK ") {
+                Ok(payload.to_string())
+            } else {
+                Err(format!("unexpected helper response: {}", response))
+            };
+        }
+    }
+
+    /// 由辅助进程读取LBLDESP/LBLDVC
+    pub fn get_info(&mut self) -> Result<PlatformInfo, String> {
+        let payload = self.request("GET_INFO", None)?;
+        let fields: Vec<&str> = payload.split(' ').collect();
+        if fields.len() != 4 {
+            return Err(format!("unexpected GET_INFO payload: {}", payload));
+        }
+        let decode_hex = |s: &str| hex::decode(s).map_err(|err| err.to_string());
+        let decode_attr = |s: &str| {
+            u32::from_str_radix(s, 16)
+                .map(VariableFlags::from_bits_retain)
+                .map_err(|err| err.to_string())
+        };
+
+        let mut platform_info = PlatformInfo::default();
+        platform_info
+            .load_vars(
+                &decode_hex(fields[0])?,
+                decode_attr(fields[1])?,
+                &decode_hex(fields[2])?,
+                decode_attr(fields[3])?,
+            )
+            .map_err(|diag| diag.to_string())?;
+        Ok(platform_info)
+    }
+
+    /// 发送图片数据，由辅助进程校验后写入ESP并更新变量
+    pub fn set_logo(&mut self, ext: &str, data: &[u8]) -> Result<(), String> {
+        self.request(&format!("SET_LOGO {} {}", ext, data.len()), Some(data))
+            .map(|_| ())
+    }
+
+    /// 恢复默认Logo
+    pub fn restore(&mut self) -> Result<(), String> {
+        self.request("RESTORE", None).map(|_| ())
+    }
+
+    /// 检查已安装的Logo是否与LBLDVC一致
+    pub fn verify(&mut self) -> Result<VerifyStatus, String> {
+        decode_verify(&self.request("VERIFY", None)?)
+    }
+
+    /// 由辅助进程读取ESP中的Logo文件
+    pub fn read_logo_files(&mut self) -> Result<Vec<(String, Vec<u8>)>, String> {
+        let payload = self.request("READ_LOGO", None)?;
+        let count: usize = payload
+            .parse()
+            .map_err(|_| format!("unexpected READ_LOGO payload: {}", payload))?;
+        let read_failed = |err: io::Error| format!("read helper response failed: {}", err);
+
+        let mut files = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).map_err(read_failed)?;
+            let (len, name) = header
+                .trim_end()
+                .split_once(' ')
+                .and_then(|(len, name)| Some((len.parse::<usize>().ok()?, name.to_string())))
+                .ok_or_else(|| format!("unexpected READ_LOGO header: {}", header.trim_end()))?;
+            if len > MAX_IMAGE_SIZE {
+                return Err(format!("invalid file size: {} bytes", len));
+            }
+            let mut data = vec![0u8; len];
+            self.stdout.read_exact(&mut data).map_err(read_failed)?;
+            files.push((name, data));
+        }
+        Ok(files)
+    }
+}
+
+impl Drop for HelperClient {
+    fn drop(&mut self) {
+        // 关闭stdin通知辅助进程结束会话
+        self.stdin.take();
+        if let Err(err) = self.child.wait() {
+            warn!("wait for helper failed: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在IEND之前插入一个tEXt块
+    fn with_text_chunk(png: &[u8], text: &[u8]) -> Vec<u8> {
+        let iend = png.len() - 12;
+        let mut chunk = Vec::new();
+        chunk.extend_from_slice(&(text.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(b"tEXt");
+        chunk.extend_from_slice(text);
+        chunk.extend_from_slice(&crc32fast::hash(&chunk[4..]).to_be_bytes());
+        [&png[..iend], &chunk, &png[iend..]].concat()
+    }
+
+    #[test]
+    fn set_logo_strips_chunks_and_trailing_data() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(8, 8, image::Rgb([200, 30, 30]))
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        let clean = png.into_inner();
+        let mut data = with_text_chunk(&clean, b"Comment\0hidden payload");
+        data.extend_from_slice(b"trailing data after IEND");
+        assert!(image::load_from_memory(&data).is_ok());
+
+        let platform_info = PlatformInfo {
+            support: vec!["png"],
+            ..Default::default()
+        };
+        let sanitized = sanitize_image(&platform_info, "png", data).unwrap();
+        assert!(sanitized.ends_with(b"IEND\xAE\x42\x60\x82"));
+        assert!(!sanitized.windows(4).any(|w| w == b"tEXt"));
+        assert!(!sanitized.windows(7).any(|w| w == b"payload"));
+
+        assert!(sanitize_image(&platform_info, "bmp", clean).is_err());
+    }
+}
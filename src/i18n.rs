@@ -1,12 +1,16 @@
-// Simple i18n module for UI strings
+//! Simple i18n module for UI strings
 
+/// 界面语言
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Lang {
+    /// English
     En,
+    /// 简体中文
     Zh,
 }
 
 impl Lang {
+    /// 根据语言代码选择语言，未知代码使用英语
     pub fn from_code(code: &str) -> Self {
         match code {
             "zh" | "zh-CN" | "zh_CN" => Lang::Zh,
@@ -17,6 +21,7 @@ impl Lang {
 
 use std::borrow::Cow;
 
+/// 查找界面文本，未定义的键原样返回
 pub fn t(lang: Lang, key: &str) -> Cow<'static, str> {
     match lang {
         Lang::En => match key {
@@ -27,6 +32,54 @@ pub fn t(lang: Lang, key: &str) -> Cow<'static, str> {
             // Support status
             "supported" => Cow::Borrowed("Your device is supported !"),
             "unsupported" => Cow::Borrowed("Your device is not supported !"),
+            "next_steps" => Cow::Borrowed("What you can do:"),
+            // Support diagnosis
+            "diag_not_uefi" => Cow::Borrowed(
+                "The system was not booted in UEFI mode (Legacy/CSM boot), so the firmware logo variables are not reachable.",
+            ),
+            "diag_not_uefi_hint" => Cow::Borrowed(
+                "Switch the boot mode to UEFI in the BIOS setup (disable CSM/Legacy) and boot the OS from a UEFI entry.",
+            ),
+            "diag_efivarfs" => Cow::Borrowed(
+                "The EFI variable filesystem (efivarfs) is not mounted, so firmware variables cannot be read.",
+            ),
+            "diag_efivarfs_hint" => {
+                Cow::Borrowed("Mount it with: mount -t efivarfs efivarfs /sys/firmware/efi/efivars")
+            }
+            "diag_lbldesp_missing" => Cow::Borrowed(
+                "The firmware does not provide the LBLDESP variable, which Lenovo uses to enable a custom boot logo.",
+            ),
+            "diag_lbldesp_missing_hint" => Cow::Borrowed(
+                "This model or BIOS version probably does not support custom logos. Check for a BIOS update, or look for a \"Boot Logo\" option in the BIOS setup.",
+            ),
+            "diag_lbldvc_missing" => Cow::Borrowed(
+                "The firmware provides LBLDESP but not the LBLDVC variable that holds the logo checksum.",
+            ),
+            "diag_lbldvc_missing_hint" => Cow::Borrowed(
+                "Update the BIOS to the latest version. If it is still missing, please open an issue with your model name.",
+            ),
+            "diag_bad_length" => Cow::Borrowed(
+                "{var} has an unexpected length: {actual} bytes (expected {expected}).",
+            ),
+            "diag_bad_length_hint" => Cow::Borrowed(
+                "This firmware uses a layout the tool does not know. Please open an issue with your model name and the length shown above.",
+            ),
+            "diag_zero_format" => {
+                Cow::Borrowed("The firmware reports no supported image formats (format mask is 0).")
+            }
+            "diag_zero_format_hint" => Cow::Borrowed(
+                "Custom logos are disabled on this firmware. Check the BIOS setup for a boot logo option or update the BIOS.",
+            ),
+            "diag_unknown_version" => Cow::Borrowed(
+                "Unknown logo checksum version {version}; the tool does not know how to sign the image.",
+            ),
+            "diag_unknown_version_hint" => Cow::Borrowed(
+                "Please open an issue with your model name and the version shown above so support can be added.",
+            ),
+            "diag_read_failed" => Cow::Borrowed("Reading {var} failed: {error}"),
+            "diag_read_failed_hint" => Cow::Borrowed(
+                "Make sure you run the program as Administrator/root and that no security software blocks firmware variable access.",
+            ),
             // UEFI logo state
             "logo_enabled" => Cow::Borrowed("UEFI Logo DIY Enabled"),
             "logo_disabled" => Cow::Borrowed("UEFI Logo DIY Disabled"),
@@ -51,7 +104,116 @@ pub fn t(lang: Lang, key: &str) -> Cow<'static, str> {
             "restore_logo_success" => Cow::Borrowed("Restore Logo Success"),
             "restore_logo_failed" => Cow::Borrowed("Restore Logo Failed"),
             // Admin prompt
-            "admin_required" => Cow::Borrowed("You need to run this program as Administrator !"),
+            "admin_required" => Cow::Borrowed(
+                "Run this program as Administrator/root to change or restore the logo.",
+            ),
+            // ACPI BGRT
+            "bgrt_title" => Cow::Borrowed("Last boot logo (ACPI BGRT)"),
+            "bgrt_desc" => Cow::Borrowed("What your firmware displayed on last boot:"),
+            "bgrt_unavailable" => Cow::Borrowed("Not available: {error}"),
+            "bgrt_export" => Cow::Borrowed("Export..."),
+            "bgrt_exported" => Cow::Borrowed("Exported to {path}"),
+            // Log panel
+            "log_title" => Cow::Borrowed("Log"),
+            "log_level" => Cow::Borrowed("Level"),
+            "log_copy" => Cow::Borrowed("Copy"),
+            "log_save" => Cow::Borrowed("Save to file..."),
+            "log_clear" => Cow::Borrowed("Clear"),
+            "log_saved" => Cow::Borrowed("Saved to {path}"),
+            // Set logo steps
+            "step_hash" => Cow::Borrowed("Hash image"),
+            "step_find_esp" => Cow::Borrowed("Find ESP"),
+            "step_mount" => Cow::Borrowed("Mount ESP"),
+            "step_remove_old" => Cow::Borrowed("Remove old logo"),
+            "step_copy" => Cow::Borrowed("Copy image"),
+            "step_unmount" => Cow::Borrowed("Unmount ESP"),
+            "step_write_lbldesp" => Cow::Borrowed("Write LBLDESP"),
+            "step_write_lbldvc" => Cow::Borrowed("Write LBLDVC"),
+            "step_verify" => Cow::Borrowed("Verify"),
+            // Change confirmation
+            "plan_title" => Cow::Borrowed("Confirm logo change"),
+            "plan_source" => Cow::Borrowed("Source"),
+            "plan_destination" => Cow::Borrowed("Destination"),
+            "plan_deleted" => Cow::Borrowed("Deleted"),
+            "plan_deleted_none" => Cow::Borrowed("nothing"),
+            "plan_deleted_unknown" => Cow::Borrowed(
+                "every file in /EFI/Lenovo/Logo (the ESP could not be read to list them)",
+            ),
+            "plan_icon_show" => Cow::Borrowed("hidden -> shown"),
+            "plan_icon_hide" => Cow::Borrowed("shown -> hidden"),
+            "plan_checkbox" => {
+                Cow::Borrowed("I understand this writes to the ESP and to firmware variables")
+            }
+            "plan_confirm" => Cow::Borrowed("Change Logo"),
+            "plan_cancel" => Cow::Borrowed("Cancel"),
+            // Installed logo
+            "installed_title" => Cow::Borrowed("Installed logo (ESP)"),
+            "installed_read" => Cow::Borrowed("Read installed logo"),
+            "installed_reading" => Cow::Borrowed("Reading the ESP..."),
+            "installed_empty" => Cow::Borrowed("No logo files on the ESP"),
+            "installed_matches" => Cow::Borrowed("matches LBLDVC"),
+            "installed_mismatch" => Cow::Borrowed("does not match LBLDVC"),
+            "installed_export" => Cow::Borrowed("Export installed logo..."),
+            "sanitize" => Cow::Borrowed("Sanitise image (recommended)"),
+            "sanitize_hint" => Cow::Borrowed(
+                "Decode the image to pixels and write a freshly encoded file, so no headers, chunks or trailing data from the original reach the ESP (protects against LogoFAIL-style images)",
+            ),
+            "convert_image" => Cow::Borrowed("Convert Image..."),
+            "convert_image_hint" => Cow::Borrowed(
+                "Open any PNG, JPEG, BMP, GIF, TGA or PCX image and save it in a format the firmware supports",
+            ),
+            // Device profile
+            "profile_known" => Cow::Borrowed("Known good device: {device}"),
+            "profile_untested" => Cow::Borrowed(
+                "Untested device: please share a compatibility report in issue #34 after trying it",
+            ),
+            "profile_verified_formats" => Cow::Borrowed("Verified formats: {formats}"),
+            "profile_broken_formats" => Cow::Borrowed("Known not to display: {formats}"),
+            "warn_broken_on_device" => Cow::Borrowed(
+                "{format} images are known not to display on {device}; they are converted to a verified format, or refused if none is known",
+            ),
+            // Compatibility report
+            "report_title" => Cow::Borrowed("Compatibility report"),
+            "report_desc" => Cow::Borrowed(
+                "Paste this into the device reports issue (#34). It contains the model, BIOS version and logo variables, but no serial numbers.",
+            ),
+            "report_markdown" => Cow::Borrowed("Generate (Markdown)"),
+            "report_json" => Cow::Borrowed("Generate (JSON)"),
+            "report_copy" => Cow::Borrowed("Copy"),
+            // Preview
+            "preview_image_size" => Cow::Borrowed("Image size"),
+            "preview_unavailable" => Cow::Borrowed("Preview not available for this format"),
+            "preview_frame" => Cow::Borrowed("Frame"),
+            "preview_flatten" => Cow::Borrowed("Flatten to this frame..."),
+            "preview_flatten_hint" => Cow::Borrowed(
+                "Save the selected frame as a single-frame GIF, transparent areas become black",
+            ),
+            "preview_animated_confirm" => {
+                Cow::Borrowed("Use it anyway (only one frame will be shown)")
+            }
+            "boot_preview_btn" => Cow::Borrowed("Preview boot screen"),
+            "boot_preview_hint" => {
+                Cow::Borrowed("Esc or click to exit, Space toggles the loading circle")
+            }
+            "warn_unsupported_format" => {
+                Cow::Borrowed("Format {format} is not supported by the firmware")
+            }
+            "warn_unreadable" => Cow::Borrowed("Cannot read the image: {error}"),
+            "warn_too_large" => {
+                Cow::Borrowed("Image is {size}, larger than the firmware maximum {max}")
+            }
+            "warn_animated" => Cow::Borrowed(
+                "Animated GIF with {frames} frames; the firmware shows at most one frame",
+            ),
+            // Boot confirmation
+            "confirm_confirmed" => Cow::Borrowed(
+                "Custom logo confirmed: the last boot displayed it (similarity {similarity}%)",
+            ),
+            "confirm_rejected" => Cow::Borrowed(
+                "Firmware rejected the custom logo: the last boot showed a different image (similarity {similarity}%)",
+            ),
+            "confirm_not_rebooted" => Cow::Borrowed("Reboot to confirm the custom logo"),
+            "confirm_unknown" => Cow::Borrowed("Cannot confirm the last boot logo: {error}"),
             _ => Cow::Owned(key.to_string()),
         },
         Lang::Zh => match key {
@@ -62,6 +224,52 @@ pub fn t(lang: Lang, key: &str) -> Cow<'static, str> {
             // Support status
             "supported" => Cow::Borrowed("您的设备是支持的！"),
             "unsupported" => Cow::Borrowed("不支持您的设备！"),
+            "next_steps" => Cow::Borrowed("您可以尝试："),
+            // Support diagnosis
+            "diag_not_uefi" => {
+                Cow::Borrowed("系统不是以UEFI模式启动（Legacy/CSM），无法访问固件中的Logo变量。")
+            }
+            "diag_not_uefi_hint" => Cow::Borrowed(
+                "在BIOS设置中将启动模式改为UEFI（关闭CSM/Legacy），并从UEFI启动项启动系统。",
+            ),
+            "diag_efivarfs" => {
+                Cow::Borrowed("EFI变量文件系统（efivarfs）未挂载，无法读取固件变量。")
+            }
+            "diag_efivarfs_hint" => Cow::Borrowed(
+                "使用以下命令挂载：mount -t efivarfs efivarfs /sys/firmware/efi/efivars",
+            ),
+            "diag_lbldesp_missing" => {
+                Cow::Borrowed("固件中没有LBLDESP变量，联想使用该变量启用自定义开机Logo。")
+            }
+            "diag_lbldesp_missing_hint" => Cow::Borrowed(
+                "该型号或BIOS版本可能不支持自定义Logo。请检查BIOS更新，或在BIOS设置中查找\"Boot Logo\"选项。",
+            ),
+            "diag_lbldvc_missing" => {
+                Cow::Borrowed("固件中有LBLDESP，但缺少保存Logo校验值的LBLDVC变量。")
+            }
+            "diag_lbldvc_missing_hint" => Cow::Borrowed(
+                "请将BIOS更新到最新版本。如果仍然缺少，请提交issue并注明您的设备型号。",
+            ),
+            "diag_bad_length" => {
+                Cow::Borrowed("{var} 长度异常：{actual} 字节（预期 {expected}）。")
+            }
+            "diag_bad_length_hint" => Cow::Borrowed(
+                "该固件使用了未知的数据格式。请提交issue并注明设备型号和上面显示的长度。",
+            ),
+            "diag_zero_format" => Cow::Borrowed("固件未报告任何支持的图片格式（格式掩码为0）。"),
+            "diag_zero_format_hint" => {
+                Cow::Borrowed("该固件关闭了自定义Logo。请在BIOS设置中查找开机Logo选项或更新BIOS。")
+            }
+            "diag_unknown_version" => {
+                Cow::Borrowed("未知的Logo校验版本 {version}，无法为图片计算校验值。")
+            }
+            "diag_unknown_version_hint" => {
+                Cow::Borrowed("请提交issue并注明设备型号和上面显示的版本号，以便添加支持。")
+            }
+            "diag_read_failed" => Cow::Borrowed("读取 {var} 失败：{error}"),
+            "diag_read_failed_hint" => Cow::Borrowed(
+                "请确认以管理员/root权限运行本程序，并且没有安全软件阻止访问固件变量。",
+            ),
             // UEFI logo state
             "logo_enabled" => Cow::Borrowed("自定义UEFI Logo已启用"),
             "logo_disabled" => Cow::Borrowed("自定义UEFI Logo未启用"),
@@ -84,7 +292,100 @@ pub fn t(lang: Lang, key: &str) -> Cow<'static, str> {
             "restore_logo_success" => Cow::Borrowed("恢复Logo成功"),
             "restore_logo_failed" => Cow::Borrowed("恢复Logo失败"),
             // Admin prompt
-            "admin_required" => Cow::Borrowed("您需要以管理员权限运行此程序！"),
+            "admin_required" => {
+                Cow::Borrowed("需要以管理员/root权限运行此程序才能设置或恢复Logo。")
+            }
+            // ACPI BGRT
+            "bgrt_title" => Cow::Borrowed("上次开机显示的Logo（ACPI BGRT）"),
+            "bgrt_desc" => Cow::Borrowed("固件上次开机时实际显示的图片："),
+            "bgrt_unavailable" => Cow::Borrowed("无法读取：{error}"),
+            "bgrt_export" => Cow::Borrowed("导出..."),
+            "bgrt_exported" => Cow::Borrowed("已导出到 {path}"),
+            // Log panel
+            "log_title" => Cow::Borrowed("日志"),
+            "log_level" => Cow::Borrowed("级别"),
+            "log_copy" => Cow::Borrowed("复制"),
+            "log_save" => Cow::Borrowed("保存到文件..."),
+            "log_clear" => Cow::Borrowed("清空"),
+            "log_saved" => Cow::Borrowed("已保存到 {path}"),
+            // Set logo steps
+            "step_hash" => Cow::Borrowed("计算校验值"),
+            "step_find_esp" => Cow::Borrowed("查找ESP分区"),
+            "step_mount" => Cow::Borrowed("挂载ESP分区"),
+            "step_remove_old" => Cow::Borrowed("删除旧Logo"),
+            "step_copy" => Cow::Borrowed("复制图片"),
+            "step_unmount" => Cow::Borrowed("卸载ESP分区"),
+            "step_write_lbldesp" => Cow::Borrowed("写入LBLDESP"),
+            "step_write_lbldvc" => Cow::Borrowed("写入LBLDVC"),
+            "step_verify" => Cow::Borrowed("校验"),
+            // Change confirmation
+            "plan_title" => Cow::Borrowed("确认设置Logo"),
+            "plan_source" => Cow::Borrowed("源文件"),
+            "plan_destination" => Cow::Borrowed("目标路径"),
+            "plan_deleted" => Cow::Borrowed("将删除"),
+            "plan_deleted_none" => Cow::Borrowed("无"),
+            "plan_deleted_unknown" => {
+                Cow::Borrowed("/EFI/Lenovo/Logo 中的所有文件（无法读取ESP以列出文件）")
+            }
+            "plan_icon_show" => Cow::Borrowed("隐藏 -> 显示"),
+            "plan_icon_hide" => Cow::Borrowed("显示 -> 隐藏"),
+            "plan_checkbox" => Cow::Borrowed("我了解此操作会写入ESP分区和固件变量"),
+            "plan_confirm" => Cow::Borrowed("设置Logo"),
+            "plan_cancel" => Cow::Borrowed("取消"),
+            // Installed logo
+            "installed_title" => Cow::Borrowed("已安装的Logo（ESP）"),
+            "installed_read" => Cow::Borrowed("读取已安装的Logo"),
+            "installed_reading" => Cow::Borrowed("正在读取ESP..."),
+            "installed_empty" => Cow::Borrowed("ESP中没有Logo文件"),
+            "installed_matches" => Cow::Borrowed("与LBLDVC一致"),
+            "installed_mismatch" => Cow::Borrowed("与LBLDVC不一致"),
+            "installed_export" => Cow::Borrowed("导出已安装的Logo..."),
+            "sanitize" => Cow::Borrowed("清洗图片（推荐）"),
+            "sanitize_hint" => Cow::Borrowed(
+                "把图片解码为像素后重新编码，原文件的文件头、数据块和多余数据都不会写入ESP（防范LogoFAIL类恶意图片）",
+            ),
+            "convert_image" => Cow::Borrowed("转换图片..."),
+            "convert_image_hint" => {
+                Cow::Borrowed("打开任意PNG、JPEG、BMP、GIF、TGA或PCX图片，保存为固件支持的格式")
+            }
+            // Device profile
+            "profile_known" => Cow::Borrowed("已验证机型：{device}"),
+            "profile_untested" => Cow::Borrowed("未测试机型：试用后请在issue #34中分享兼容性报告"),
+            "profile_verified_formats" => Cow::Borrowed("已验证的格式：{formats}"),
+            "profile_broken_formats" => Cow::Borrowed("已知无法显示：{formats}"),
+            "warn_broken_on_device" => Cow::Borrowed(
+                "已知{device}无法显示{format}图片，写入前会转换为已验证的格式，没有已验证的格式时拒绝写入",
+            ),
+            // Compatibility report
+            "report_title" => Cow::Borrowed("兼容性报告"),
+            "report_desc" => Cow::Borrowed(
+                "粘贴到机型报告issue（#34）。包含机型、BIOS版本和Logo变量，不包含序列号。",
+            ),
+            "report_markdown" => Cow::Borrowed("生成（Markdown）"),
+            "report_json" => Cow::Borrowed("生成（JSON）"),
+            "report_copy" => Cow::Borrowed("复制"),
+            // Preview
+            "preview_image_size" => Cow::Borrowed("图片分辨率"),
+            "preview_unavailable" => Cow::Borrowed("该格式无法预览"),
+            "preview_frame" => Cow::Borrowed("帧"),
+            "preview_flatten" => Cow::Borrowed("压平为该帧..."),
+            "preview_flatten_hint" => Cow::Borrowed("把选中的帧另存为单帧GIF，透明部分变为黑色"),
+            "preview_animated_confirm" => Cow::Borrowed("仍然使用（只会显示其中一帧）"),
+            "boot_preview_btn" => Cow::Borrowed("预览开机画面"),
+            "boot_preview_hint" => Cow::Borrowed("按Esc或点击退出，空格键切换加载图标"),
+            "warn_unsupported_format" => Cow::Borrowed("固件不支持 {format} 格式"),
+            "warn_unreadable" => Cow::Borrowed("无法读取图片：{error}"),
+            "warn_too_large" => Cow::Borrowed("图片为 {size}，超过固件最大分辨率 {max}"),
+            "warn_animated" => Cow::Borrowed("这是包含 {frames} 帧的GIF动图，固件最多只显示一帧"),
+            // Boot confirmation
+            "confirm_confirmed" => {
+                Cow::Borrowed("已确认：上次开机显示了自定义Logo（相似度 {similarity}%）")
+            }
+            "confirm_rejected" => Cow::Borrowed(
+                "固件拒绝了自定义Logo：上次开机显示的是其他图片（相似度 {similarity}%）",
+            ),
+            "confirm_not_rebooted" => Cow::Borrowed("重新启动后即可确认自定义Logo是否生效"),
+            "confirm_unknown" => Cow::Borrowed("无法确认上次开机的Logo：{error}"),
             _ => Cow::Owned(key.to_string()),
         },
     }
@@ -0,0 +1,459 @@
+//! 图片预处理模块
+//!
+//! 读取图片尺寸，检查图片是否适合固件，按配置把超出固件最大分辨率的图片缩小，
+//! 写入前重新编码为固件容易解码的形式，清洗模式下所有格式都完全重新生成
+
+use std::fmt;
+use std::io::Cursor;
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{AnimationDecoder, DynamicImage, ImageFormat, ImageReader};
+use log::info;
+use serde::Deserialize;
+
+use crate::encoders::{self, PcxDepth};
+
+/// 编码JPEG时使用的质量（缩放、保守重新编码、清洗和格式转换都经过 `encode`）
+const JPEG_QUALITY: u8 = 90;
+
+/// 图片超过固件最大分辨率时的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizePolicy {
+    /// 不缩放，图片超过最大分辨率时报错
+    #[default]
+    None,
+    /// 保持宽高比缩小到最大分辨率以内，未超出时原样使用
+    Fit,
+}
+
+/// 扩展名对应的图片格式，PCX不受image库支持，返回None
+pub fn image_format(ext: &str) -> Option<ImageFormat> {
+    match ext.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+        "png" => Some(ImageFormat::Png),
+        "bmp" => Some(ImageFormat::Bmp),
+        "gif" => Some(ImageFormat::Gif),
+        "tga" => Some(ImageFormat::Tga),
+        _ => None,
+    }
+}
+
+/// 读取图片尺寸，只解析文件头
+pub fn dimensions(data: &[u8], ext: &str) -> Result<(u32, u32), String> {
+    if ext.eq_ignore_ascii_case("pcx") {
+        return pcx_dimensions(data);
+    }
+    let format = image_format(ext).ok_or_else(|| format!("unsupported image format: {}", ext))?;
+    ImageReader::with_format(Cursor::new(data), format)
+        .into_dimensions()
+        .map_err(|err| format!("cannot read {} image: {}", ext, err))
+}
+
+/// PCX文件头中的窗口坐标（Xmin, Ymin, Xmax, Ymax）
+fn pcx_dimensions(data: &[u8]) -> Result<(u32, u32), String> {
+    if data.len() < 128 || data[0] != 0x0A {
+        return Err("not a valid pcx file".to_string());
+    }
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]) as u32;
+    let (xmin, ymin, xmax, ymax) = (read_u16(4), read_u16(6), read_u16(8), read_u16(10));
+    if xmax < xmin || ymax < ymin {
+        return Err("invalid pcx window".to_string());
+    }
+    Ok((xmax - xmin + 1, ymax - ymin + 1))
+}
+
+/// GIF的帧数
+pub fn gif_frame_count(data: &[u8]) -> Result<usize, String> {
+    let decoder =
+        GifDecoder::new(Cursor::new(data)).map_err(|err| format!("cannot read gif: {}", err))?;
+    let mut count = 0;
+    for frame in decoder.into_frames() {
+        frame.map_err(|err| format!("cannot read gif frame {}: {}", count, err))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// GIF的第 `index` 帧（从0开始），已按各帧的处置方式合成为完整画面
+pub fn gif_frame(data: &[u8], index: usize) -> Result<DynamicImage, String> {
+    let decoder =
+        GifDecoder::new(Cursor::new(data)).map_err(|err| format!("cannot read gif: {}", err))?;
+    let frame = decoder
+        .into_frames()
+        .nth(index)
+        .ok_or_else(|| format!("gif has no frame {}", index))?
+        .map_err(|err| format!("cannot read gif frame {}: {}", index, err))?;
+    Ok(DynamicImage::ImageRgba8(frame.into_buffer()))
+}
+
+/// 把多帧GIF压平为第 `index` 帧的单帧GIF，透明部分叠加到黑色上
+pub fn flatten_gif(data: &[u8], index: usize) -> Result<Vec<u8>, String> {
+    let flattened = encoders::write_gif(&gif_frame(data, index)?)?;
+    info!("flattened gif to frame {}", index);
+    Ok(flattened)
+}
+
+/// JPEG是否为渐进式编码（SOF2等帧头），部分固件只能解码基线JPEG
+pub fn is_progressive_jpeg(data: &[u8]) -> bool {
+    // 跳过SOI，逐段读取标记直到帧头或扫描数据开始
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        match marker {
+            // 填充字节
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // SOF2/SOF6/SOF10/SOF14：渐进式
+            0xC2 | 0xC6 | 0xCA | 0xCE => return true,
+            // 其他帧头或扫描开始：不是渐进式
+            0xC0 | 0xC1 | 0xC3 | 0xC5 | 0xC7 | 0xC9 | 0xCB | 0xCD | 0xCF | 0xDA => return false,
+            _ => {}
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        pos += 2 + len;
+    }
+    false
+}
+
+/// 把JPEG重新编码为基线JPEG
+pub fn to_baseline_jpeg(data: &[u8]) -> Result<Vec<u8>, String> {
+    encode(&decode(data, "jpg")?, "jpg")
+}
+
+/// 图片检查发现的问题
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageWarning {
+    /// 固件不支持该格式
+    UnsupportedFormat(String),
+    /// 无法读取图片
+    Unreadable(String),
+    /// 超过固件最大分辨率
+    TooLarge {
+        /// 图片宽度
+        width: u32,
+        /// 图片高度
+        height: u32,
+        /// 固件最大宽度
+        max_width: u32,
+        /// 固件最大高度
+        max_height: u32,
+    },
+    /// 多帧GIF，固件最多只显示其中一帧
+    Animated {
+        /// 帧数
+        frames: usize,
+    },
+    /// 机型数据库记录该格式在本机型上无法显示
+    BrokenOnDevice {
+        /// 图片格式
+        format: String,
+        /// 机型名称
+        device: String,
+    },
+}
+
+impl ImageWarning {
+    /// 是否应阻止写入，动图只是提醒
+    pub fn is_blocking(&self) -> bool {
+        !matches!(self, ImageWarning::Animated { .. })
+    }
+}
+
+impl fmt::Display for ImageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageWarning::UnsupportedFormat(ext) => {
+                write!(f, "format {} is not supported by the firmware", ext)
+            }
+            ImageWarning::Unreadable(err) => write!(f, "{}", err),
+            ImageWarning::TooLarge {
+                width,
+                height,
+                max_width,
+                max_height,
+            } => write!(
+                f,
+                "image is {}x{}, larger than the firmware maximum {}x{}",
+                width, height, max_width, max_height
+            ),
+            ImageWarning::Animated { frames } => write!(
+                f,
+                "animated gif with {} frames, the firmware shows at most one",
+                frames
+            ),
+            ImageWarning::BrokenOnDevice { format, device } => {
+                write!(f, "format {} is known not to display on {}", format, device)
+            }
+        }
+    }
+}
+
+/// 图片检查结果
+#[derive(Clone, Debug, Default)]
+pub struct ImageCheck {
+    /// 图片尺寸，无法读取时为None
+    pub dimensions: Option<(u32, u32)>,
+    /// 发现的问题
+    pub warnings: Vec<ImageWarning>,
+}
+
+impl ImageCheck {
+    /// 是否有阻止写入的问题
+    pub fn is_blocked(&self) -> bool {
+        self.warnings.iter().any(ImageWarning::is_blocking)
+    }
+}
+
+/// 检查图片格式、尺寸和帧数是否适合固件
+///
+/// # 参数
+/// * `data` - 图片数据
+/// * `ext` - 图片扩展名
+/// * `support` - 固件支持的格式
+/// * `max_width`/`max_height` - 固件允许的最大分辨率
+pub fn check(
+    data: &[u8],
+    ext: &str,
+    support: &[&str],
+    max_width: u32,
+    max_height: u32,
+) -> ImageCheck {
+    let mut result = ImageCheck::default();
+    let ext = ext.to_ascii_lowercase();
+    let format = if ext == "jpeg" { "jpg" } else { ext.as_str() };
+    if !support.contains(&format) {
+        result
+            .warnings
+            .push(ImageWarning::UnsupportedFormat(ext.clone()));
+    }
+
+    match dimensions(data, &ext) {
+        Ok((width, height)) => {
+            result.dimensions = Some((width, height));
+            if width > max_width || height > max_height {
+                result.warnings.push(ImageWarning::TooLarge {
+                    width,
+                    height,
+                    max_width,
+                    max_height,
+                });
+            }
+        }
+        Err(err) => result.warnings.push(ImageWarning::Unreadable(err)),
+    }
+
+    if format == "gif" && result.dimensions.is_some() {
+        match gif_frame_count(data) {
+            Ok(frames) if frames > 1 => result.warnings.push(ImageWarning::Animated { frames }),
+            Ok(_) => {}
+            Err(err) => result.warnings.push(ImageWarning::Unreadable(err)),
+        }
+    }
+    result
+}
+
+/// 解码图片，PCX由本模块自行解码
+pub fn decode(data: &[u8], ext: &str) -> Result<DynamicImage, String> {
+    if ext.eq_ignore_ascii_case("pcx") {
+        return decode_pcx(data);
+    }
+    let format = image_format(ext).ok_or_else(|| format!("cannot decode {} images", ext))?;
+    image::load_from_memory_with_format(data, format)
+        .map_err(|err| format!("decode image failed: {}", err))
+}
+
+/// 解码8位调色板（1个平面）或24位（3个平面）的PCX，其他布局返回错误
+fn decode_pcx(data: &[u8]) -> Result<DynamicImage, String> {
+    let (width, height) = pcx_dimensions(data)?;
+    let (bits, planes) = (data[3], data[65]);
+    let bytes_per_line = u16::from_le_bytes([data[66], data[67]]) as usize;
+    if bits != 8 || !(planes == 1 || planes == 3) {
+        return Err(format!(
+            "unsupported pcx layout: {} bits, {} planes",
+            bits, planes
+        ));
+    }
+    if bytes_per_line < width as usize {
+        return Err("invalid pcx line length".to_string());
+    }
+
+    // 按RLE展开所有扫描线
+    let line_len = bytes_per_line * planes as usize;
+    let total = line_len
+        .checked_mul(height as usize)
+        .filter(|&total| total <= data.len() * 63)
+        .ok_or("pcx image larger than its data")?;
+    let mut pixels = Vec::with_capacity(total);
+    let mut pos = 128;
+    while pixels.len() < total {
+        let byte = *data.get(pos).ok_or("truncated pcx data")?;
+        pos += 1;
+        if data[2] == 1 && byte >= 0xC0 {
+            let value = *data.get(pos).ok_or("truncated pcx data")?;
+            pos += 1;
+            let run = ((byte & 0x3F) as usize).min(total - pixels.len());
+            pixels.resize(pixels.len() + run, value);
+        } else {
+            pixels.push(byte);
+        }
+    }
+
+    let palette = if planes == 1 {
+        // 文件末尾的256色调色板，前面是0x0C标记
+        let start = data
+            .len()
+            .checked_sub(768)
+            .filter(|&start| start > pos && data[start - 1] == 0x0C)
+            .ok_or("pcx has no 256-color palette")?;
+        Some(&data[start..])
+    } else {
+        None
+    };
+    let img = image::RgbImage::from_fn(width, height, |x, y| {
+        let line = &pixels[y as usize * line_len..][..line_len];
+        let x = x as usize;
+        match palette {
+            Some(palette) => {
+                let index = line[x] as usize * 3;
+                image::Rgb([palette[index], palette[index + 1], palette[index + 2]])
+            }
+            None => image::Rgb([
+                line[x],
+                line[bytes_per_line + x],
+                line[2 * bytes_per_line + x],
+            ]),
+        }
+    });
+    Ok(DynamicImage::ImageRgb8(img))
+}
+
+/// 完全解码为像素后用本程序的编码器重新生成文件（清洗模式）
+///
+/// LogoFAIL等攻击利用固件图片解析器的漏洞，恶意数据藏在文件头、数据块或文件末尾。
+/// 重新编码后写入ESP的每个字节都由本程序生成，原文件的文件头、数据块和多余数据都不会保留。
+/// 多帧GIF只保留第一帧。
+pub fn sanitize(data: Vec<u8>, ext: &str) -> Result<Vec<u8>, String> {
+    let img = decode(&data, ext).map_err(|err| format!("cannot sanitize image: {}", err))?;
+    let sanitized = encode(&img, ext)?;
+    info!(
+        "sanitized {} image: {} -> {} bytes",
+        ext,
+        data.len(),
+        sanitized.len()
+    );
+    Ok(sanitized)
+}
+
+/// 按缩放策略准备要写入ESP的图片数据
+///
+/// 图片在最大分辨率以内时原样返回，保证同一输入总是得到相同的字节
+///
+/// # 参数
+/// * `data` - 原始图片数据
+/// * `ext` - 图片扩展名
+/// * `policy` - 超出最大分辨率时的处理方式
+/// * `max_width`/`max_height` - 固件允许的最大分辨率
+pub fn prepare(
+    data: Vec<u8>,
+    ext: &str,
+    policy: ResizePolicy,
+    max_width: u32,
+    max_height: u32,
+) -> Result<Vec<u8>, String> {
+    let (width, height) = dimensions(&data, ext)?;
+    if width <= max_width && height <= max_height {
+        return Ok(data);
+    }
+
+    match policy {
+        ResizePolicy::None => Err(format!(
+            "image is {}x{}, larger than the firmware maximum {}x{}",
+            width, height, max_width, max_height
+        )),
+        ResizePolicy::Fit => {
+            let img = decode(&data, ext)?;
+            let resized = img.resize(max_width, max_height, FilterType::Lanczos3);
+            info!(
+                "resized image from {}x{} to {}x{}",
+                width,
+                height,
+                resized.width(),
+                resized.height()
+            );
+            encode(&resized, ext)
+        }
+    }
+}
+
+/// 把通过检查的图片重新编码为固件最容易解码的形式
+///
+/// UEFI中的图片解码器比桌面程序挑剔得多，因此：
+/// * JPEG：基线编码（不使用渐进式），RGB
+/// * PNG：每通道8位、不隔行，只在原图有透明通道时保留RGBA
+/// * BMP：24位、自下而上、不压缩
+///
+/// 重新编码不保留EXIF、ICC配置、文本块等元数据和辅助块。其他格式原样返回。
+/// 同一输入总是得到相同的字节，`apply` 据此判断是否需要写入。
+pub fn normalize(data: Vec<u8>, ext: &str) -> Result<Vec<u8>, String> {
+    if !matches!(
+        image_format(ext),
+        Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Bmp)
+    ) {
+        return Ok(data);
+    }
+    let normalized = encode(&decode(&data, ext)?, ext)?;
+    info!(
+        "normalized {} image: {} -> {} bytes",
+        ext,
+        data.len(),
+        normalized.len()
+    );
+    Ok(normalized)
+}
+
+/// 把图片转换为另一种格式，用于生成固件支持但手头没有的格式
+///
+/// PCX在颜色不超过256种时写为8位调色板，否则写为24位
+pub fn convert(data: &[u8], ext: &str, target: &str) -> Result<Vec<u8>, String> {
+    let img = decode(data, ext)?;
+    let converted = encode(&img, target)?;
+    info!(
+        "converted {} image to {}: {} -> {} bytes",
+        ext,
+        target,
+        data.len(),
+        converted.len()
+    );
+    Ok(converted)
+}
+
+/// 按固件最容易解码的形式编码，JPEG/BMP/TGA/GIF/PCX不保留透明通道
+fn encode(img: &DynamicImage, ext: &str) -> Result<Vec<u8>, String> {
+    if ext.eq_ignore_ascii_case("pcx") {
+        let depth = if encoders::color_count(img, 256) <= 256 {
+            PcxDepth::Indexed8
+        } else {
+            PcxDepth::Rgb24
+        };
+        return encoders::write_pcx(img, depth);
+    }
+    let format = image_format(ext).ok_or_else(|| format!("cannot encode {} images", ext))?;
+    let mut out = Cursor::new(Vec::new());
+    let result = match format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+            .encode_image(&encoders::flatten_on_black(img)),
+        ImageFormat::Png if img.color().has_alpha() => {
+            DynamicImage::ImageRgba8(img.to_rgba8()).write_to(&mut out, format)
+        }
+        ImageFormat::Tga => return encoders::write_tga(img),
+        ImageFormat::Gif => return encoders::write_gif(img),
+        _ => DynamicImage::ImageRgb8(encoders::flatten_on_black(img)).write_to(&mut out, format),
+    };
+    result.map_err(|err| format!("encode image failed: {}", err))?;
+    Ok(out.into_inner())
+}
@@ -0,0 +1,95 @@
+//! 已安装Logo模块
+//!
+//! 只读地查看ESP中 `/EFI/Lenovo/Logo` 目录的文件，计算哈希并与LBLDVC比较，
+//! 可以导出正在使用的Logo。挂载方式与写入时相同，但不会删除或修改任何文件。
+
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::esp_partition::read_logo_files;
+use crate::imaging;
+use crate::lenlogo::{LOGO_DIR, PlatformInfo};
+
+/// ESP中的一个Logo文件
+#[derive(Clone, Debug)]
+pub struct InstalledLogo {
+    /// 文件名
+    pub name: String,
+    /// 文件内容
+    pub data: Vec<u8>,
+    /// 文件内容的SHA-256（hex）
+    pub sha256: String,
+    /// 是否与LBLDVC中的校验值一致，即固件会显示的文件
+    pub matches_lbldvc: bool,
+}
+
+impl InstalledLogo {
+    /// 读取ESP中的Logo文件（需要管理员/root权限）
+    pub fn read_all(platform_info: &PlatformInfo) -> Result<Vec<Self>, String> {
+        let files = read_logo_files().ok_or("cannot read the ESP")?;
+        Ok(Self::from_files(files, platform_info))
+    }
+
+    /// 由已读取的文件计算哈希和校验结果，按文件名排序
+    pub fn from_files(files: Vec<(String, Vec<u8>)>, platform_info: &PlatformInfo) -> Vec<Self> {
+        let mut logos: Vec<Self> = files
+            .into_iter()
+            .map(|(name, data)| Self {
+                sha256: hex::encode(Sha256::digest(&data)),
+                matches_lbldvc: platform_info.checksum_var(&data) == Some(platform_info.lbldvc_var),
+                name,
+                data,
+            })
+            .collect();
+        logos.sort_by(|a, b| a.name.cmp(&b.name));
+        logos
+    }
+
+    /// 固件会显示的文件：与LBLDVC一致的文件，只有一个文件时就是该文件
+    pub fn active(logos: &[Self]) -> Option<&Self> {
+        logos
+            .iter()
+            .find(|logo| logo.matches_lbldvc)
+            .or(match logos {
+                [only] => Some(only),
+                _ => None,
+            })
+    }
+
+    /// ESP中的完整路径
+    pub fn path(&self) -> String {
+        format!("{}/{}", LOGO_DIR, self.name)
+    }
+
+    /// 文件扩展名（小写）
+    pub fn ext(&self) -> String {
+        Path::new(&self.name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    }
+
+    /// 导出文件，扩展名与原文件相同时保存原始数据，否则转换格式
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let normalize = |ext: &str| match ext.to_ascii_lowercase().as_str() {
+            "jpeg" => "jpg".to_string(),
+            ext => ext.to_string(),
+        };
+        let target = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(normalize)
+            .unwrap_or_default();
+        let result = if target == normalize(&self.ext()) {
+            std::fs::write(path, &self.data).map_err(|err| err.to_string())
+        } else {
+            imaging::decode(&self.data, &self.ext()).and_then(|img| {
+                // 转换为RGB以便保存为JPEG等不支持透明通道的格式
+                img.to_rgb8().save(path).map_err(|err| err.to_string())
+            })
+        };
+        result.map_err(|err| format!("export to {} failed: {}", path.display(), err))
+    }
+}
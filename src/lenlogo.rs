@@ -1,18 +1,36 @@
+//! Logo设置核心模块
+//!
+//! 读取和写入LBLDESP/LBLDVC变量，计算Logo校验值
+
 use crc32fast::Hasher;
+use efivar::VarManager;
 use efivar::efi::{Variable, VariableFlags};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use sha2::{Digest, Sha256};
-use std::fs::File;
-use std::io;
-use std::io::Read;
+use std::fmt;
 use std::path::Path;
 use std::str::FromStr;
 
-use crate::esp_partition::{copy_file_to_esp, delete_logo_path};
+use crate::esp_partition::{delete_logo_path, read_logo_files, write_file_to_esp};
+use crate::platform::{NativePlatform, PlatformOps};
+use crate::progress::{self, Step};
 
 #[cfg(target_os = "linux")]
 use crate::platform::linux::LinuxPlatform;
 
+/// Logo设置变量（启用标志、最大分辨率、支持的格式）
+pub const LBLDESP_NAME: &str = "LBLDESP-871455D0-5576-4FB8-9865-AF0824463B9E";
+/// Logo校验变量（校验方式版本和图片校验值）
+pub const LBLDVC_NAME: &str = "LBLDVC-871455D1-5576-4FB8-9865-AF0824463C9F";
+
+/// ESP分区中的Logo目录
+pub const LOGO_DIR: &str = "/EFI/Lenovo/Logo";
+
+/// LBLDESP/LBLDVC 的预期属性：NV + BS + RT
+pub const EXPECTED_ATTR: VariableFlags = VariableFlags::NON_VOLATILE
+    .union(VariableFlags::BOOTSERVICE_ACCESS)
+    .union(VariableFlags::RUNTIME_ACCESS);
+
 /// 跨平台的EFI变量写入包装函数
 /// Linux下需要处理immutable属性，Windows下直接调用
 fn with_efi_var_writable<F>(var_name: &str, f: F) -> Result<(), String>
@@ -32,14 +50,212 @@ where
     }
 }
 
-pub(crate) struct PlatformInfo {
-    pub(crate) enable: u8,
-    pub(crate) width: u32,
-    pub(crate) height: u32,
-    pub(crate) version: u32,
-    pub(crate) support: Vec<&'static str>,
-    pub(crate) lbldesp_var: [u8; 10],
-    pub(crate) lbldvc_var: [u8; 40],
+/// 以原有属性写入EFI变量，并回读确认数据和属性均已生效
+///
+/// Linux下会在写入前后处理efivarfs文件的immutable标志
+pub fn write_efi_var(
+    varman: &mut dyn VarManager,
+    var: &Variable,
+    attr: VariableFlags,
+    data: &[u8],
+) -> Result<(), String> {
+    // efivarfs中的文件名为 Name-guid（guid小写），与Variable的Display一致
+    let var_name = var.to_string();
+    with_efi_var_writable(&var_name, || match varman.write(var, attr, data) {
+        Ok(rt) => {
+            debug!("write {}: {:?}", var_name, rt);
+            Ok(())
+        }
+        Err(err) => Err(format!("write {} failed: {}", var_name, err)),
+    })?;
+
+    match varman.read(var) {
+        Ok((buffer, read_attr)) => {
+            if buffer != data {
+                return Err(format!(
+                    "verify {} failed: expected {}, read back {}",
+                    var_name,
+                    hex::encode(data),
+                    hex::encode(&buffer)
+                ));
+            }
+            if read_attr != attr {
+                return Err(format!(
+                    "verify {} failed: expected attributes 0x{:x}, read back 0x{:x}",
+                    var_name,
+                    attr.bits(),
+                    read_attr.bits()
+                ));
+            }
+            debug!("verify {}: ok", var_name);
+            Ok(())
+        }
+        Err(err) => Err(format!("verify {} failed: {}", var_name, err)),
+    }
+}
+
+/// 设备不受支持的具体原因
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SupportDiagnosis {
+    /// 系统不是以UEFI模式启动（Legacy/CSM）
+    NotUefi,
+    /// EFI变量接口不可用（Linux下efivarfs未挂载）
+    EfivarfsNotMounted,
+    /// 固件中不存在LBLDESP变量
+    LbldespMissing,
+    /// 固件中不存在LBLDVC变量
+    LbldvcMissing,
+    /// 变量长度与预期不符
+    BadLength {
+        /// 变量名
+        var: &'static str,
+        /// 预期长度
+        expected: usize,
+        /// 实际长度
+        actual: usize,
+    },
+    /// LBLDESP中的格式掩码为0，没有可用的图片格式
+    ZeroFormatMask,
+    /// LBLDVC中的版本号未知，不知道该使用哪种校验方式
+    UnknownVersion(u32),
+    /// 读取变量时出现其他错误（如权限不足）
+    ReadFailed {
+        /// 变量名
+        var: &'static str,
+        /// 错误信息
+        error: String,
+    },
+}
+
+impl SupportDiagnosis {
+    /// 对应的i18n键，界面用 `<key>` 显示说明，`<key>_hint` 显示处理建议
+    pub fn i18n_key(&self) -> &'static str {
+        match self {
+            SupportDiagnosis::NotUefi => "diag_not_uefi",
+            SupportDiagnosis::EfivarfsNotMounted => "diag_efivarfs",
+            SupportDiagnosis::LbldespMissing => "diag_lbldesp_missing",
+            SupportDiagnosis::LbldvcMissing => "diag_lbldvc_missing",
+            SupportDiagnosis::BadLength { .. } => "diag_bad_length",
+            SupportDiagnosis::ZeroFormatMask => "diag_zero_format",
+            SupportDiagnosis::UnknownVersion(_) => "diag_unknown_version",
+            SupportDiagnosis::ReadFailed { .. } => "diag_read_failed",
+        }
+    }
+
+    /// 将说明文本中的占位符替换为实际值
+    pub fn fill(&self, text: &str) -> String {
+        match self {
+            SupportDiagnosis::BadLength {
+                var,
+                expected,
+                actual,
+            } => text
+                .replace("{var}", var)
+                .replace("{expected}", &expected.to_string())
+                .replace("{actual}", &actual.to_string()),
+            SupportDiagnosis::UnknownVersion(version) => {
+                text.replace("{version}", &format!("0x{:x}", version))
+            }
+            SupportDiagnosis::ReadFailed { var, error } => {
+                text.replace("{var}", var).replace("{error}", error)
+            }
+            _ => text.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SupportDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupportDiagnosis::NotUefi => write!(f, "system is not booted in UEFI mode"),
+            SupportDiagnosis::EfivarfsNotMounted => write!(f, "efivarfs is not mounted"),
+            SupportDiagnosis::LbldespMissing => write!(f, "LBLDESP variable not found"),
+            SupportDiagnosis::LbldvcMissing => write!(f, "LBLDVC variable not found"),
+            SupportDiagnosis::BadLength {
+                var,
+                expected,
+                actual,
+            } => write!(f, "{} has length {}, expected {}", var, actual, expected),
+            SupportDiagnosis::ZeroFormatMask => write!(f, "LBLDESP format mask is zero"),
+            SupportDiagnosis::UnknownVersion(version) => {
+                write!(f, "unknown LBLDVC version 0x{:x}", version)
+            }
+            SupportDiagnosis::ReadFailed { var, error } => {
+                write!(f, "read {} failed: {}", var, error)
+            }
+        }
+    }
+}
+
+/// 已安装Logo的校验结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// Logo已启用，ESP中的文件与LBLDVC中的校验值一致
+    Ok {
+        /// 校验通过的文件名
+        file: String,
+    },
+    /// LBLDESP中的启用标志为0
+    NotEnabled,
+    /// ESP分区中没有Logo文件
+    NoLogoFile,
+    /// ESP分区中的Logo文件与LBLDVC中的校验值都不一致
+    ChecksumMismatch {
+        /// Logo目录中的文件名
+        files: Vec<String>,
+    },
+    /// 无法挂载或读取ESP分区
+    EspUnavailable,
+}
+
+impl VerifyStatus {
+    /// 稳定的结果代码，用于JSON输出
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifyStatus::Ok { .. } => "ok",
+            VerifyStatus::NotEnabled => "not_enabled",
+            VerifyStatus::NoLogoFile => "no_logo_file",
+            VerifyStatus::ChecksumMismatch { .. } => "checksum_mismatch",
+            VerifyStatus::EspUnavailable => "esp_unavailable",
+        }
+    }
+}
+
+impl fmt::Display for VerifyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyStatus::Ok { file } => write!(f, "ok: {} matches LBLDVC", file),
+            VerifyStatus::NotEnabled => write!(f, "custom logo is not enabled in LBLDESP"),
+            VerifyStatus::NoLogoFile => write!(f, "no logo file found on the ESP"),
+            VerifyStatus::ChecksumMismatch { files } => {
+                write!(f, "checksum in LBLDVC does not match {}", files.join(", "))
+            }
+            VerifyStatus::EspUnavailable => write!(f, "cannot read the ESP"),
+        }
+    }
+}
+
+/// 从LBLDESP/LBLDVC中解析出的平台Logo信息
+#[derive(Clone)]
+pub struct PlatformInfo {
+    /// 自定义Logo启用标志（LBLDESP第0字节）
+    pub enable: u8,
+    /// Logo最大宽度
+    pub width: u32,
+    /// Logo最大高度
+    pub height: u32,
+    /// 校验方式版本（LBLDVC前4字节），0x20000为CRC32，0x20003为SHA256
+    pub version: u32,
+    /// 固件支持的图片格式（扩展名）
+    pub support: Vec<&'static str>,
+    /// LBLDESP原始内容
+    pub lbldesp_var: [u8; 10],
+    /// LBLDVC原始内容
+    pub lbldvc_var: [u8; 40],
+    /// LBLDESP的变量属性
+    pub lbldesp_attr: VariableFlags,
+    /// LBLDVC的变量属性
+    pub lbldvc_attr: VariableFlags,
 }
 
 impl Default for PlatformInfo {
@@ -52,65 +268,144 @@ impl Default for PlatformInfo {
             support: Vec::new(),
             lbldesp_var: [0u8; 10],
             lbldvc_var: [0u8; 40],
+            lbldesp_attr: EXPECTED_ATTR,
+            lbldvc_attr: EXPECTED_ATTR,
         }
     }
 }
 
 impl PlatformInfo {
-    pub(crate) fn get_info(&mut self) -> bool {
-        let varman = efivar::system();
+    /// 读取LBLDESP/LBLDVC，不支持时返回具体原因
+    pub fn get_info(&mut self) -> Result<(), SupportDiagnosis> {
+        if !NativePlatform::is_uefi_boot() {
+            return Err(SupportDiagnosis::NotUefi);
+        }
+        if !NativePlatform::efivars_available() {
+            return Err(SupportDiagnosis::EfivarfsNotMounted);
+        }
 
-        let esp_var = Variable::from_str("LBLDESP-871455D0-5576-4FB8-9865-AF0824463B9E").unwrap();
+        let varman = efivar::system();
 
-        match varman.read(&esp_var) {
-            Ok((esp_buffer, _attr)) => {
-                if esp_buffer.len() != 10 {
-                    error!("read lbldesp_var failed: buffer length is not 10");
-                    return false;
-                }
-                self.enable = esp_buffer[0];
-                self.width = u32::from_le_bytes(esp_buffer[1..5].try_into().unwrap());
-                self.height = u32::from_le_bytes(esp_buffer[5..9].try_into().unwrap());
-                self.support = Self::support_format(esp_buffer[9]);
-                self.lbldesp_var = <[u8; 10]>::try_from(esp_buffer).unwrap();
+        let esp_var = Variable::from_str(LBLDESP_NAME).unwrap();
+        let (esp_buffer, esp_attr) = match varman.read(&esp_var) {
+            Ok(value) => value,
+            Err(efivar::Error::VarNotFound { .. }) => {
+                return Err(SupportDiagnosis::LbldespMissing);
             }
             Err(err) => {
-                error!("read lbldesp_var failed: {}", err);
-                return false;
+                return Err(SupportDiagnosis::ReadFailed {
+                    var: "LBLDESP",
+                    error: err.to_string(),
+                });
             }
-        }
+        };
 
-        let dvc_var = Variable::from_str("LBLDVC-871455D1-5576-4FB8-9865-AF0824463C9F").unwrap();
-        match varman.read(&dvc_var) {
-            Ok((dvc_buffer, _attr)) => {
-                if dvc_buffer.len() != 40 {
-                    error!("read lbldvc_var failed: buffer length is not 40");
-                    return false;
-                }
-                self.version = u32::from_le_bytes(dvc_buffer[0..4].try_into().unwrap());
-                self.lbldvc_var = <[u8; 40]>::try_from(dvc_buffer).unwrap();
+        let dvc_var = Variable::from_str(LBLDVC_NAME).unwrap();
+        let (dvc_buffer, dvc_attr) = match varman.read(&dvc_var) {
+            Ok(value) => value,
+            Err(efivar::Error::VarNotFound { .. }) => {
+                return Err(SupportDiagnosis::LbldvcMissing);
             }
             Err(err) => {
-                error!("read lbldvc_var failed: {}", err);
-                return false;
+                return Err(SupportDiagnosis::ReadFailed {
+                    var: "LBLDVC",
+                    error: err.to_string(),
+                });
             }
+        };
+
+        self.load_vars(&esp_buffer, esp_attr, &dvc_buffer, dvc_attr)
+    }
+
+    /// 解析LBLDESP/LBLDVC的原始内容
+    pub fn load_vars(
+        &mut self,
+        esp_buffer: &[u8],
+        esp_attr: VariableFlags,
+        dvc_buffer: &[u8],
+        dvc_attr: VariableFlags,
+    ) -> Result<(), SupportDiagnosis> {
+        if esp_buffer.len() != 10 {
+            return Err(SupportDiagnosis::BadLength {
+                var: "LBLDESP",
+                expected: 10,
+                actual: esp_buffer.len(),
+            });
         }
-        true
+        self.enable = esp_buffer[0];
+        self.width = u32::from_le_bytes(esp_buffer[1..5].try_into().unwrap());
+        self.height = u32::from_le_bytes(esp_buffer[5..9].try_into().unwrap());
+        self.support = Self::support_format(esp_buffer[9]);
+        self.lbldesp_var = <[u8; 10]>::try_from(esp_buffer).unwrap();
+        self.lbldesp_attr = esp_attr;
+        Self::check_attr("LBLDESP", esp_attr);
+
+        if dvc_buffer.len() != 40 {
+            return Err(SupportDiagnosis::BadLength {
+                var: "LBLDVC",
+                expected: 40,
+                actual: dvc_buffer.len(),
+            });
+        }
+        self.version = u32::from_le_bytes(dvc_buffer[0..4].try_into().unwrap());
+        self.lbldvc_var = <[u8; 40]>::try_from(dvc_buffer).unwrap();
+        self.lbldvc_attr = dvc_attr;
+        Self::check_attr("LBLDVC", dvc_attr);
+
+        if self.lbldesp_var[9] == 0 {
+            return Err(SupportDiagnosis::ZeroFormatMask);
+        }
+        if self.version != 0x20003 && self.version != 0x20000 {
+            return Err(SupportDiagnosis::UnknownVersion(self.version));
+        }
+        Ok(())
     }
 
-    pub(crate) fn set_logo(&mut self, img_path: &String) -> bool {
-        // 复制文件到ESP分区
+    /// 将图片文件写入ESP分区并更新LBLDESP/LBLDVC
+    pub fn set_logo(&mut self, img_path: &str) -> bool {
         let file_path = Path::new(img_path);
-        let file_extension = file_path.extension().unwrap().to_str().unwrap();
+        let file_extension = match file_path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => ext,
+            None => {
+                error!("image has no file extension: {}", img_path);
+                return false;
+            }
+        };
         debug!("file_extension: {}", file_extension);
 
-        let dst_path = format!(
-            r"/EFI/Lenovo/Logo/mylogo_{}x{}.{}",
-            self.width, self.height, file_extension
-        );
+        match std::fs::read(file_path) {
+            Ok(data) => self.set_logo_bytes(&data, file_extension),
+            Err(e) => {
+                error!("read error {}: {}", img_path, e);
+                false
+            }
+        }
+    }
+
+    /// 将图片内容写入ESP分区并更新LBLDESP/LBLDVC
+    ///
+    /// 不记录已应用状态，需要开机检查时由调用方使用 [`crate::state::record`]
+    pub fn set_logo_bytes(&mut self, data: &[u8], file_extension: &str) -> bool {
+        // 修改logocheck - 根据version选择SHA256或CRC32，先计算以免写入一半才发现版本不支持
+        let step = progress::begin(Step::Hash);
+        let dvc_buffer = match self.checksum_var(data) {
+            Some(buffer) => {
+                step.finish(true);
+                buffer
+            }
+            None => {
+                step.finish(false);
+                error!("unsupported version: 0x{:x}", self.version);
+                return false;
+            }
+        };
+        debug!("dvc_buffer: {:?}", dvc_buffer);
+
+        // 写入文件到ESP分区
+        let dst_path = format!("{}/{}", LOGO_DIR, self.logo_file_name(file_extension));
         info!("target path: {}", dst_path);
 
-        if copy_file_to_esp(img_path, &dst_path) == false {
+        if !write_file_to_esp(data, &dst_path) {
             error!("copy file failed");
             return false;
         }
@@ -118,27 +413,13 @@ impl PlatformInfo {
         let mut varman = efivar::system();
 
         // 修改logoinfo
-        let mut esp_buffer = self.lbldesp_var.clone();
-        esp_buffer[0] = 1;
-        let esp_var = Variable::from_str("LBLDESP-871455D0-5576-4FB8-9865-AF0824463B9E").unwrap();
-
-        // 在Linux下需要先移除immutable属性
-        let write_result = with_efi_var_writable(
-            "LBLDESP-871455d0-5576-4fb8-9865-af0824463b9e",
-            || match varman.write(
-                &esp_var,
-                VariableFlags::from_bits(0x7).unwrap(),
-                &esp_buffer,
-            ) {
-                Ok(rt) => {
-                    debug!("write lbldesp_var: {:?}", rt);
-                    Ok(())
-                }
-                Err(err) => Err(format!("write lbldesp_var failed: {}", err)),
-            },
-        );
+        let esp_buffer = self.enabled_var();
+        let esp_var = Variable::from_str(LBLDESP_NAME).unwrap();
 
-        match write_result {
+        let step = progress::begin(Step::WriteLbldesp);
+        let result = write_efi_var(varman.as_mut(), &esp_var, self.lbldesp_attr, &esp_buffer);
+        step.finish(result.is_ok());
+        match result {
             Ok(_) => {
                 self.enable = 1;
                 self.lbldesp_var = esp_buffer;
@@ -149,74 +430,90 @@ impl PlatformInfo {
             }
         }
 
-        // 修改logocheck - 根据version选择SHA256或CRC32
-        let mut dvc_buffer = self.lbldvc_var.clone();
+        let dvc_var = Variable::from_str(LBLDVC_NAME).unwrap();
 
+        let step = progress::begin(Step::WriteLbldvc);
+        let result = write_efi_var(varman.as_mut(), &dvc_var, self.lbldvc_attr, &dvc_buffer);
+        step.finish(result.is_ok());
+        match result {
+            Ok(_) => {
+                self.lbldvc_var = dvc_buffer;
+            }
+            Err(err) => {
+                error!("{}", err);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// 写入ESP时使用的文件名，如 `mylogo_1920x1080.png`
+    pub fn logo_file_name(&self, file_extension: &str) -> String {
+        format!("mylogo_{}x{}.{}", self.width, self.height, file_extension)
+    }
+
+    /// 启用自定义Logo后的LBLDESP内容
+    pub fn enabled_var(&self) -> [u8; 10] {
+        let mut esp_buffer = self.lbldesp_var;
+        esp_buffer[0] = 1;
+        esp_buffer
+    }
+
+    /// 根据version计算图片对应的LBLDVC内容，version未知时返回None
+    pub fn checksum_var(&self, data: &[u8]) -> Option<[u8; 40]> {
+        let mut dvc_buffer = self.lbldvc_var;
         if self.version == 0x20003 {
             // version 0x20003: 使用SHA256 (32字节)
-            let sha256_bytes;
-            match calculate_sha256(img_path) {
-                Ok(sha256) => {
-                    // 将sha256十六进制字符串转化为十六进制序列
-                    sha256_bytes = hex::decode(sha256).unwrap();
-                }
-                Err(e) => {
-                    error!("read error {}: {}", img_path, e);
-                    return false;
-                }
-            }
+            let sha256_bytes = Sha256::digest(data);
             dvc_buffer[4..36].clone_from_slice(&sha256_bytes);
             debug!("sha256_bytes: {:?}", sha256_bytes);
         } else if self.version == 0x20000 {
             // version 0x20000: 使用CRC32 (4字节)
-            match calculate_crc32_first_512(img_path) {
-                Ok(crc32) => {
-                    dvc_buffer[4..8].clone_from_slice(&crc32.to_le_bytes());
-                    debug!("crc32: 0x{:08x}", crc32);
-                }
-                Err(e) => {
-                    error!("read error {}: {}", img_path, e);
-                    return false;
-                }
-            }
+            let crc32 = calculate_crc32_first_512(data);
+            dvc_buffer[4..8].clone_from_slice(&crc32.to_le_bytes());
+            debug!("crc32: 0x{:08x}", crc32);
         } else {
-            error!("unsupported version: 0x{:x}", self.version);
-            return false;
+            return None;
         }
-        debug!("dvc_buffer: {:?}", dvc_buffer);
+        Some(dvc_buffer)
+    }
 
-        let dvc_var = Variable::from_str("LBLDVC-871455D1-5576-4FB8-9865-AF0824463C9F").unwrap();
-
-        // 在Linux下需要先移除immutable属性
-        let write_result = with_efi_var_writable(
-            "LBLDVC-871455d1-5576-4fb8-9865-af0824463c9f",
-            || match varman.write(
-                &dvc_var,
-                VariableFlags::from_bits(0x7).unwrap(),
-                &dvc_buffer,
-            ) {
-                Ok(rt) => {
-                    debug!("write lbldvc_var: {:?}", rt);
-                    Ok(())
-                }
-                Err(err) => Err(format!("write lbldvc_var failed: {}", err)),
-            },
-        );
+    /// 检查ESP分区中已安装的Logo是否已启用且与LBLDVC中的校验值一致
+    pub fn verify_logo(&self) -> VerifyStatus {
+        if self.enable == 0 {
+            return VerifyStatus::NotEnabled;
+        }
+        self.verify_files(read_logo_files().as_deref())
+    }
 
-        match write_result {
-            Ok(_) => {
-                self.lbldvc_var = dvc_buffer;
-            }
-            Err(err) => {
-                error!("{}", err);
-                return false;
+    /// 用已经读取的Logo目录内容做校验，避免重复挂载ESP
+    ///
+    /// # 参数
+    /// * `files` - `read_logo_files` 的结果，`None` 表示无法读取ESP
+    pub fn verify_files(&self, files: Option<&[(String, Vec<u8>)]>) -> VerifyStatus {
+        if self.enable == 0 {
+            return VerifyStatus::NotEnabled;
+        }
+        let files = match files {
+            Some(files) => files,
+            None => return VerifyStatus::EspUnavailable,
+        };
+        if files.is_empty() {
+            return VerifyStatus::NoLogoFile;
+        }
+        for (name, data) in files {
+            if self.checksum_var(data) == Some(self.lbldvc_var) {
+                return VerifyStatus::Ok { file: name.clone() };
             }
         }
-        true
+        VerifyStatus::ChecksumMismatch {
+            files: files.iter().map(|(name, _)| name.clone()).collect(),
+        }
     }
 
-    pub(crate) fn restore_logo(&mut self) -> bool {
-        //
+    /// 删除ESP分区中的Logo并清除LBLDESP启用标志和LBLDVC校验值
+    pub fn restore_logo(&mut self) -> bool {
         let mut status = true;
         if !delete_logo_path() {
             error!("delete logo path failed");
@@ -225,30 +522,14 @@ impl PlatformInfo {
 
         let mut varman = efivar::system();
         // 修改logoinfo
-        let mut esp_buffer = self.lbldesp_var.clone();
+        let mut esp_buffer = self.lbldesp_var;
         if esp_buffer[0] != 0 {
             esp_buffer[0] = 0;
-            let esp_var =
-                Variable::from_str("LBLDESP-871455D0-5576-4FB8-9865-AF0824463B9E").unwrap();
-
-            // 在Linux下需要先移除immutable属性
-            let write_result =
-                with_efi_var_writable("LBLDESP-871455d0-5576-4fb8-9865-af0824463b9e", || {
-                    match varman.write(
-                        &esp_var,
-                        VariableFlags::from_bits(0x7).unwrap(),
-                        &esp_buffer,
-                    ) {
-                        Ok(rt) => {
-                            debug!("write lbldesp_var: {:?}", rt);
-                            Ok(())
-                        }
-                        Err(err) => Err(format!("write lbldesp_var failed: {}", err)),
-                    }
-                });
+            let esp_var = Variable::from_str(LBLDESP_NAME).unwrap();
 
-            match write_result {
+            match write_efi_var(varman.as_mut(), &esp_var, self.lbldesp_attr, &esp_buffer) {
                 Ok(_) => {
+                    self.enable = 0;
                     self.lbldesp_var = esp_buffer;
                 }
                 Err(err) => {
@@ -259,7 +540,7 @@ impl PlatformInfo {
         }
 
         // 修改logocheck - 根据version选择清零范围
-        let mut dvc_buffer = self.lbldvc_var.clone();
+        let mut dvc_buffer = self.lbldvc_var;
         let need_clear = if self.version == 0x20000 {
             // version 0x20000
             dvc_buffer[4..8] != [0u8; 4]
@@ -277,25 +558,9 @@ impl PlatformInfo {
                 dvc_buffer[4..40].clone_from_slice(&[0u8; 36]);
             }
 
-            let dvc_var =
-                Variable::from_str("LBLDVC-871455D1-5576-4FB8-9865-AF0824463C9F").unwrap();
-
-            let write_result = with_efi_var_writable(
-                "LBLDVC-871455d1-5576-4fb8-9865-af0824463c9f",
-                || match varman.write(
-                    &dvc_var,
-                    VariableFlags::from_bits(0x7).unwrap(),
-                    &dvc_buffer,
-                ) {
-                    Ok(rt) => {
-                        debug!("write lbldvc_var: {:?}", rt);
-                        Ok(())
-                    }
-                    Err(err) => Err(format!("write lbldvc_var failed: {}", err)),
-                },
-            );
+            let dvc_var = Variable::from_str(LBLDVC_NAME).unwrap();
 
-            match write_result {
+            match write_efi_var(varman.as_mut(), &dvc_var, self.lbldvc_attr, &dvc_buffer) {
                 Ok(_) => {
                     self.lbldvc_var = dvc_buffer;
                 }
@@ -305,9 +570,22 @@ impl PlatformInfo {
                 }
             }
         }
+
         status
     }
 
+    /// 属性与预期（NV+BS+RT）不同时给出警告，写入时仍沿用原有属性
+    fn check_attr(name: &str, attr: VariableFlags) {
+        if attr != EXPECTED_ATTR {
+            warn!(
+                "{} attributes are 0x{:x}, expected 0x{:x}; keeping the firmware's attributes",
+                name,
+                attr.bits(),
+                EXPECTED_ATTR.bits()
+            );
+        }
+    }
+
     fn support_format(support: u8) -> Vec<&'static str> {
         let mut support_types = Vec::new();
         if support & 0x1 == 0x1 {
@@ -332,29 +610,8 @@ impl PlatformInfo {
     }
 }
 
-fn calculate_sha256(file_path: &str) -> io::Result<String> {
-    let mut file = File::open(file_path)?;
-    let mut sha256 = Sha256::new();
-    let mut buffer = [0; 1024];
-
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-
-        if bytes_read == 0 {
-            break;
-        }
-
-        sha256.update(&buffer[..bytes_read]);
-    }
-    Ok(format!("{:x}", sha256.finalize()))
-}
-
-fn calculate_crc32_first_512(file_path: &str) -> io::Result<u32> {
-    let mut file = File::open(file_path)?;
-    let mut buffer = [0u8; 512];
-    let bytes_read = file.read(&mut buffer)?;
-
+fn calculate_crc32_first_512(data: &[u8]) -> u32 {
     let mut hasher = Hasher::new();
-    hasher.update(&buffer[..bytes_read]);
-    Ok(hasher.finalize())
+    hasher.update(&data[..data.len().min(512)]);
+    hasher.finalize()
 }
@@ -0,0 +1,64 @@
+//! Lenovo UEFI Boot Logo Changer
+//!
+//! Library behind the `lenovo-logo-changer` GUI and the `lenovo-logo-changer-cli` tool.
+//! It reads and writes Lenovo's `LBLDESP`/`LBLDVC` firmware variables and places the logo
+//! image on the EFI System Partition (ESP).
+//!
+//! The main entry points are:
+//!
+//! * [`PlatformInfo`] - read the firmware's logo settings, change, restore and verify the logo;
+//! * [`operations`] - the complete set/restore operations used by the GUI and CLI;
+//! * [`bgrt`] - the logo the firmware actually displayed on the last boot (ACPI BGRT);
+//! * [`confirm`] - after a reboot, check whether the firmware displayed the custom logo;
+//! * [`edk2_bmp`] - check a BMP against the rules of EDK2's BMP decoder and explain each violation;
+//! * [`encoders`] - conservative TGA, PCX and GIF writers for converting images to any advertised format;
+//! * [`installed`] - read-only inspection and export of the logo files on the ESP;
+//! * [`apply`] - idempotent desired-state apply driven by a small TOML config;
+//! * [`rotation`] - pick the next logo from a library folder (random, sequential or by date);
+//! * [`state`] - the last applied logo, used to re-apply it after a BIOS update resets it;
+//! * [`platform`] - platform backends for privileges, ESP access and EFI variable handling;
+//! * [`esp_partition`] - ESP file access on the native platform;
+//! * [`helper`] - the privileged helper protocol used to run changes through `pkexec`;
+//! * [`status`] - the versioned JSON status report printed by `lenovo-logo-changer-cli --json`;
+//! * [`profiles`] - the built-in database of reported devices and their image quirks;
+//! * [`report`] - the compatibility report for device reports (Markdown or JSON, no serial numbers);
+//! * [`doctor`] - read-only environment checks with a pass/warn/fail report;
+//! * [`imaging`] - decode, check, scale and re-encode images into a form the firmware can decode;
+//! * [`progress`] - per-step progress events emitted while the logo is being changed;
+//! * [`i18n`] - English and Chinese UI strings shared by the GUI and CLI;
+//! * [`log_buffer`] - a logger that also keeps recent records in memory for the GUI log panel.
+//!
+//! ```no_run
+//! use lenovo_logo_changer::PlatformInfo;
+//!
+//! let mut info = PlatformInfo::default();
+//! match info.get_info() {
+//!     Ok(()) => println!("max logo size {}x{}", info.width, info.height),
+//!     Err(diag) => eprintln!("not supported: {}", diag),
+//! }
+//! ```
+#![warn(missing_docs)]
+
+pub mod apply;
+pub mod bgrt;
+pub mod confirm;
+pub mod doctor;
+pub mod edk2_bmp;
+pub mod encoders;
+pub mod esp_partition;
+pub mod helper;
+pub mod i18n;
+pub mod imaging;
+pub mod installed;
+pub mod lenlogo;
+pub mod log_buffer;
+pub mod operations;
+pub mod platform;
+pub mod profiles;
+pub mod progress;
+pub mod report;
+pub mod rotation;
+pub mod state;
+pub mod status;
+
+pub use lenlogo::{PlatformInfo, SupportDiagnosis, VerifyStatus};
@@ -0,0 +1,124 @@
+//! 日志缓冲模块
+//!
+//! Windows发布版没有控制台窗口，Linux从启动器打开时也看不到stderr，
+//! 因此界面把日志同时交给env_logger输出到stderr，并在内存中保留最近的记录，
+//! 供日志面板显示和保存，方便用户提交问题时附上日志。
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// 本程序的日志target前缀，记录其debug级别的日志，依赖库只记录info及以上
+const OWN_TARGET: &str = "lenovo_logo_changer";
+
+/// 一条日志
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// 距离程序启动的时间
+    pub elapsed: Duration,
+    /// 日志级别
+    pub level: Level,
+    /// 日志target（模块路径）
+    pub target: String,
+    /// 日志内容
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>8.3}s {:<5} {}] {}",
+            self.elapsed.as_secs_f64(),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// 同时输出到env_logger并保留最近记录的日志实现
+pub struct BufferLogger {
+    inner: env_logger::Logger,
+    started: Instant,
+    capacity: usize,
+    entries: Mutex<VecDeque<LogEntry>>,
+}
+
+impl BufferLogger {
+    /// 安装为全局日志实现，最多保留 `capacity` 条记录
+    ///
+    /// stderr输出仍由 `RUST_LOG` 控制（默认info）
+    pub fn init(capacity: usize) -> Result<&'static Self, String> {
+        let inner =
+            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+                .format_timestamp(None)
+                .format_target(false)
+                .build();
+        let logger: &'static Self = Box::leak(Box::new(Self {
+            inner,
+            started: Instant::now(),
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }));
+        log::set_logger(logger).map_err(|err| err.to_string())?;
+        log::set_max_level(LevelFilter::Debug.max(logger.inner.filter()));
+        Ok(logger)
+    }
+
+    /// 当前保留的所有记录
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// 清空记录
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<LogEntry>> {
+        // 记录日志时panic不应让日志面板失效
+        self.entries.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn buffered(metadata: &Metadata) -> bool {
+        if metadata.target().starts_with(OWN_TARGET) {
+            metadata.level() <= Level::Debug
+        } else {
+            metadata.level() <= Level::Info
+        }
+    }
+}
+
+impl Log for BufferLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        Self::buffered(metadata) || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+        if !Self::buffered(record.metadata()) {
+            return;
+        }
+        let entry = LogEntry {
+            elapsed: self.started.elapsed(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        let mut entries = self.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
@@ -3,10 +3,7 @@
     windows_subsystem = "windows"
 )] // hide console window on Windows in release
 
-mod esp_partition;
-mod i18n;
-mod lenlogo;
-mod platform;
+use std::sync::mpsc;
 
 use eframe::egui;
 use eframe::egui::Color32;
@@ -15,21 +12,30 @@ use egui::FontFamily::Proportional;
 use egui::FontId;
 use egui::RichText;
 use egui::TextStyle::{Body, Button, Heading, Monospace, Small};
-use i18n::{Lang, t};
-use lenlogo::PlatformInfo;
-use platform::{NativePlatform, PlatformOps};
+use lenovo_logo_changer::bgrt::Bgrt;
+use lenovo_logo_changer::confirm::{self, Confirmation};
+use lenovo_logo_changer::i18n::{Lang, t};
+use lenovo_logo_changer::imaging::{self, ImageCheck, ImageWarning};
+use lenovo_logo_changer::installed::InstalledLogo;
+use lenovo_logo_changer::log_buffer::BufferLogger;
+use lenovo_logo_changer::operations::{self, OperationResult, SetLogoPlan};
+use lenovo_logo_changer::platform::{NativePlatform, PlatformOps};
+use lenovo_logo_changer::profiles::{self, DeviceProfile};
+use lenovo_logo_changer::progress::{self, ProgressEvent, Step, StepStatus};
+use lenovo_logo_changer::report::CompatReport;
+use lenovo_logo_changer::{PlatformInfo, SupportDiagnosis, helper};
 use poll_promise::Promise;
 
 fn main() -> Result<(), eframe::Error> {
-    let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp(None)
-        .format_target(false)
-        .try_init();
+    let logger = BufferLogger::init(LOG_CAPACITY)
+        .inspect_err(|err| eprintln!("Install logger failed: {}", err))
+        .ok();
+
     let icon = include_bytes!("../assets/icon.png");
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([600.0, 420.0])
+            .with_inner_size([600.0, 600.0])
             .with_min_inner_size([600.0, 420.0])
             .with_icon(eframe::icon_data::from_png_bytes(icon).unwrap()),
         ..Default::default()
@@ -37,105 +43,344 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Lenovo UEFI Boot Logo Changer",
         options,
-        Box::new(|cc| Ok(Box::new(MyApp::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(MyApp::new(cc, logger)))),
     )
 }
 
-// 定义Logo操作的结果
-struct LogoOperationResult {
-    success: bool,
-    new_loading_icon_state: bool,
-    // 返回更新后的平台信息
-    enable: u8,
-    lbldesp_var: [u8; 10],
-    lbldvc_var: [u8; 40],
+/// 可以转换为其他格式的图片（image库能解码的格式）
+const CONVERT_SOURCE_FORMATS: [&str; 7] = ["png", "jpg", "jpeg", "bmp", "gif", "tga", "pcx"];
+
+/// 日志面板保留的最大记录数
+const LOG_CAPACITY: usize = 2000;
+
+/// 确认对话框中变量内容每行显示的字节数
+const HEX_DIFF_LINE_BYTES: usize = 20;
+
+/// 预览区域大小
+const PREVIEW_SIZE: egui::Vec2 = egui::vec2(240.0, 150.0);
+
+/// 预览纹理的最大边长，避免大图占用过多显存
+const PREVIEW_TEXTURE_MAX: u32 = 480;
+
+/// 选择的图片的预览和检查结果
+struct Preview {
+    check: ImageCheck,
+    // 无法解码的图片没有纹理
+    texture: Option<egui::TextureHandle>,
+    // 用户已确认使用多帧GIF
+    animated_confirmed: bool,
+    // 多帧GIF的数据和预览中显示的帧，用于压平为单帧
+    gif_data: Option<Vec<u8>>,
+    frame: usize,
 }
 
-// 在后台线程执行设置Logo操作
-fn perform_set_logo_operation(
-    img_path: String,
-    show_loading_icon: bool,
-    lbldesp_var: [u8; 10],
-    lbldvc_var: [u8; 40],
-) -> LogoOperationResult {
-    // 先设置加载图标
-    let loading_icon_result = NativePlatform::set_loading_icon(show_loading_icon);
-    let new_loading_icon_state = NativePlatform::get_loading_icon();
-
-    if loading_icon_result {
-        log::info!("Loading icon change success");
-    } else {
-        log::error!("Loading icon change failed");
+/// 预览区域中点击的操作
+enum PreviewAction {
+    None,
+    BootPreview,
+    // 把多帧GIF压平为选中的帧
+    FlattenGif,
+}
+
+impl Preview {
+    fn load(ctx: &egui::Context, path: &str, platform_info: &PlatformInfo) -> Self {
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                return Self {
+                    check: ImageCheck {
+                        dimensions: None,
+                        warnings: vec![ImageWarning::Unreadable(format!("read {}: {}", path, err))],
+                    },
+                    texture: None,
+                    animated_confirmed: false,
+                    gif_data: None,
+                    frame: 0,
+                };
+            }
+        };
+        let mut check = imaging::check(
+            &data,
+            ext,
+            &platform_info.support,
+            platform_info.width,
+            platform_info.height,
+        );
+        if let Some(profile) = profiles::current()
+            && profile.is_broken_format(ext)
+        {
+            check.warnings.push(ImageWarning::BrokenOnDevice {
+                format: ext.to_ascii_lowercase(),
+                device: profile.name.clone(),
+            });
+        }
+        let texture = match imaging::decode(&data, ext) {
+            Ok(img) => Some(Self::texture(ctx, &img)),
+            Err(err) => {
+                log::info!("No preview for {}: {}", path, err);
+                None
+            }
+        };
+        let animated = check
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, ImageWarning::Animated { .. }));
+        Self {
+            check,
+            texture,
+            animated_confirmed: false,
+            gif_data: animated.then_some(data),
+            frame: 0,
+        }
     }
 
-    // 执行设置Logo操作
-    let mut temp_info = PlatformInfo::default();
-    temp_info.lbldesp_var = lbldesp_var;
-    temp_info.lbldvc_var = lbldvc_var;
-    temp_info.width = u32::from_le_bytes(lbldesp_var[1..5].try_into().unwrap());
-    temp_info.height = u32::from_le_bytes(lbldesp_var[5..9].try_into().unwrap());
-    temp_info.version = u32::from_le_bytes(lbldvc_var[0..4].try_into().unwrap());
+    fn texture(ctx: &egui::Context, img: &image::DynamicImage) -> egui::TextureHandle {
+        let img = img
+            .thumbnail(PREVIEW_TEXTURE_MAX, PREVIEW_TEXTURE_MAX)
+            .to_rgba8();
+        let image = egui::ColorImage::from_rgba_unmultiplied(
+            [img.width() as usize, img.height() as usize],
+            img.as_raw(),
+        );
+        ctx.load_texture("preview", image, Default::default())
+    }
 
-    let success = temp_info.set_logo(&img_path);
+    /// 预览多帧GIF的另一帧
+    fn show_frame(&mut self, ctx: &egui::Context, frame: usize) {
+        let Some(data) = &self.gif_data else {
+            return;
+        };
+        match imaging::gif_frame(data, frame) {
+            Ok(img) => {
+                self.texture = Some(Self::texture(ctx, &img));
+                self.frame = frame;
+            }
+            Err(err) => log::warn!("Cannot preview gif frame {}: {}", frame, err),
+        }
+    }
 
-    if success {
-        log::info!("Change logo success");
-    } else {
-        log::error!("Change logo failed");
+    /// 没有阻止写入的问题，且动图已确认
+    fn can_change(&self) -> bool {
+        let animated = self
+            .check
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, ImageWarning::Animated { .. }));
+        !self.check.is_blocked() && (!animated || self.animated_confirmed)
     }
+}
 
-    // 在后台重新获取平台信息，避免在UI线程中读取
-    let mut updated_info = PlatformInfo::default();
-    updated_info.get_info();
+/// 加载图标中心的纵向位置（占屏幕高度的比例）
+const SPINNER_CENTER_Y: f32 = 0.75;
 
-    LogoOperationResult {
-        success,
-        new_loading_icon_state,
-        enable: updated_info.enable,
-        lbldesp_var: updated_info.lbldesp_var,
-        lbldvc_var: updated_info.lbldvc_var,
-    }
+/// 加载图标半径（占屏幕高度的比例）
+const SPINNER_RADIUS: f32 = 1.0 / 36.0;
+
+/// 加载图标的点数
+const SPINNER_DOTS: usize = 5;
+
+/// 提示文字显示的秒数
+const BOOT_PREVIEW_HINT_SECS: f64 = 3.0;
+
+/// 全屏模拟开机画面：固件把Logo按原始像素居中显示在黑色屏幕上，
+/// Windows显示加载图标时在Logo下方转圈
+struct BootPreview {
+    texture: egui::TextureHandle,
+    logo_size: egui::Vec2,
+    spinner: bool,
+    started: Option<f64>,
 }
 
-// 在后台线程执行恢复Logo操作
-fn perform_restore_logo_operation(
-    lbldesp_var: [u8; 10],
-    lbldvc_var: [u8; 40],
-) -> LogoOperationResult {
-    // 设置加载图标为启用
-    let loading_icon_result = NativePlatform::set_loading_icon(true);
-    let new_loading_icon_state = NativePlatform::get_loading_icon();
-
-    if loading_icon_result {
-        log::info!("Restore loading icon success");
-    } else {
-        log::error!("Restore loading icon failed");
+impl BootPreview {
+    /// 按设置Logo时的处理流程准备图片，预览实际写入ESP的内容
+    fn load(
+        ctx: &egui::Context,
+        path: &str,
+        sanitize: bool,
+        spinner: bool,
+    ) -> Result<Self, String> {
+        let (ext, data) = operations::prepare_image(path, sanitize)?;
+        let img = imaging::decode(&data, &ext)?.to_rgba8();
+        let image = egui::ColorImage::from_rgba_unmultiplied(
+            [img.width() as usize, img.height() as usize],
+            img.as_raw(),
+        );
+        Ok(Self {
+            texture: ctx.load_texture("boot_preview", image, Default::default()),
+            logo_size: egui::vec2(img.width() as f32, img.height() as f32),
+            spinner,
+            started: None,
+        })
     }
 
-    // 执行恢复Logo操作
-    let mut temp_info = PlatformInfo::default();
-    temp_info.lbldesp_var = lbldesp_var;
-    temp_info.lbldvc_var = lbldvc_var;
-    temp_info.version = u32::from_le_bytes(lbldvc_var[0..4].try_into().unwrap());
+    /// 绘制预览，返回false表示退出预览
+    fn show(&mut self, ctx: &egui::Context, lang: Lang) -> bool {
+        let (now, exit, toggle_spinner) = ctx.input(|i| {
+            (
+                i.time,
+                i.key_pressed(egui::Key::Escape) || i.pointer.any_click(),
+                i.key_pressed(egui::Key::Space),
+            )
+        });
+        if exit {
+            return false;
+        }
+        if toggle_spinner {
+            self.spinner = !self.spinner;
+        }
+        let started = *self.started.get_or_insert(now);
 
-    let success = temp_info.restore_logo();
+        // 屏幕的物理分辨率，即固件启动时使用的分辨率
+        let rect = ctx.content_rect();
+        let panel = ctx
+            .input(|i| {
+                let viewport = i.viewport();
+                viewport
+                    .monitor_size
+                    .zip(viewport.native_pixels_per_point)
+                    .map(|(size, ppp)| size * ppp)
+            })
+            .unwrap_or(rect.size() * ctx.pixels_per_point());
+        let scale = (rect.width() / panel.x).min(rect.height() / panel.y);
+        let screen = egui::Rect::from_center_size(rect.center(), panel * scale);
 
-    if success {
-        log::info!("Restore logo success");
-    } else {
-        log::error!("Restore logo failed");
+        egui::CentralPanel::default()
+            .frame(egui::Frame::NONE.fill(Color32::BLACK))
+            .show(ctx, |ui| {
+                let painter = ui.painter();
+                painter.image(
+                    self.texture.id(),
+                    egui::Rect::from_center_size(screen.center(), self.logo_size * scale),
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+
+                if self.spinner {
+                    let center = egui::pos2(
+                        screen.center().x,
+                        screen.top() + screen.height() * SPINNER_CENTER_Y,
+                    );
+                    let radius = screen.height() * SPINNER_RADIUS;
+                    for dot in 0..SPINNER_DOTS {
+                        // 各点依次错开，模拟Windows加载图标的追赶效果
+                        let angle = (now * 4.0) as f32 - dot as f32 * 0.35;
+                        let offset = egui::vec2(angle.cos(), angle.sin()) * radius;
+                        painter.circle_filled(center + offset, radius / 8.0, Color32::WHITE);
+                    }
+                    ctx.request_repaint();
+                }
+
+                if now - started < BOOT_PREVIEW_HINT_SECS {
+                    painter.text(
+                        screen.center_bottom() - egui::vec2(0.0, 16.0),
+                        egui::Align2::CENTER_BOTTOM,
+                        t(lang, "boot_preview_hint"),
+                        FontId::proportional(14.0),
+                        Color32::GRAY,
+                    );
+                    ctx.request_repaint();
+                }
+            });
+        true
     }
+}
 
-    // 在后台重新获取平台信息，避免在UI线程中读取
-    let mut updated_info = PlatformInfo::default();
-    updated_info.get_info();
+/// 日志面板
+struct LogPanel {
+    logger: &'static BufferLogger,
+    // 显示该级别及更严重的日志
+    level: log::Level,
+    save_result: Option<Result<String, String>>,
+}
 
-    LogoOperationResult {
-        success,
-        new_loading_icon_state,
-        enable: updated_info.enable,
-        lbldesp_var: updated_info.lbldesp_var,
-        lbldvc_var: updated_info.lbldvc_var,
+impl LogPanel {
+    fn new(logger: &'static BufferLogger) -> Self {
+        Self {
+            logger,
+            level: log::Level::Info,
+            save_result: None,
+        }
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui, lang: Lang) {
+        ui.collapsing(t(lang, "log_title"), |ui| {
+            let entries: Vec<_> = self
+                .logger
+                .entries()
+                .into_iter()
+                .filter(|entry| entry.level <= self.level)
+                .collect();
+            let text = || {
+                entries
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            ui.horizontal(|ui| {
+                ui.label(format!("{} :", t(lang, "log_level")));
+                for level in [
+                    log::Level::Error,
+                    log::Level::Warn,
+                    log::Level::Info,
+                    log::Level::Debug,
+                ] {
+                    ui.radio_value(&mut self.level, level, level.as_str());
+                }
+                ui.separator();
+                if ui.button(t(lang, "log_copy").as_ref()).clicked() {
+                    ui.ctx().copy_text(text());
+                }
+                if ui.button(t(lang, "log_save").as_ref()).clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Log", &["log", "txt"])
+                        .set_file_name("lenovo-logo-changer.log")
+                        .save_file()
+                {
+                    self.save_result = Some(
+                        std::fs::write(&path, text() + "\n")
+                            .map(|()| path.display().to_string())
+                            .map_err(|err| format!("save {} failed: {}", path.display(), err)),
+                    );
+                }
+                if ui.button(t(lang, "log_clear").as_ref()).clicked() {
+                    self.logger.clear();
+                }
+            });
+            match &self.save_result {
+                Some(Ok(path)) => {
+                    ui.colored_label(
+                        Color32::LIGHT_GREEN,
+                        t(lang, "log_saved").replace("{path}", path),
+                    );
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(Color32::LIGHT_RED, err);
+                }
+                None => {}
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(180.0)
+                .auto_shrink([false, true])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in &entries {
+                        let color = match entry.level {
+                            log::Level::Error => Color32::LIGHT_RED,
+                            log::Level::Warn => Color32::YELLOW,
+                            log::Level::Info => ui.visuals().text_color(),
+                            _ => Color32::GRAY,
+                        };
+                        ui.label(RichText::new(entry.to_string()).monospace().color(color));
+                    }
+                });
+        });
     }
 }
 
@@ -143,52 +388,129 @@ fn perform_restore_logo_operation(
 struct MyApp {
     language: String,
     is_admin: bool,
+    // 非管理员时能否通过pkexec启动特权辅助进程
+    can_elevate: bool,
     is_support: bool,
+    support_diagnosis: Option<SupportDiagnosis>,
     is_loading_icon: bool,
     platform_info: PlatformInfo,
     last_set_logo: i8,
     last_restore_logo: i8,
     set_loading_icon: bool,
+    // 清洗模式：完全解码后重新生成图片，默认开启
+    sanitize: bool,
     picked_path: Option<String>,
+    preview: Option<Preview>,
+    // 全屏开机画面预览
+    boot_preview: Option<BootPreview>,
+    // 正在后台计算的修改（需要读取ESP）
+    set_logo_plan_promise: Option<Promise<Result<SetLogoPlan, String>>>,
+    // 确认对话框中显示的修改，对话框打开时为Some
+    set_logo_plan: Option<SetLogoPlan>,
+    // 用户已勾选确认
+    set_logo_plan_checked: bool,
+    set_logo_plan_error: Option<String>,
+    // 已确认的修改，下一帧开始写入
+    confirmed_plan: Option<SetLogoPlan>,
+    // 设置Logo的进度事件和各步骤状态（顺序同Step::ALL）
+    progress_receiver: Option<mpsc::Receiver<ProgressEvent>>,
+    progress_steps: [Option<StepStatus>; Step::ALL.len()],
+    // 日志面板，安装日志实现失败时为None
+    log_panel: Option<LogPanel>,
     // Promise用于异步操作
-    set_logo_promise: Option<Promise<LogoOperationResult>>,
-    restore_logo_promise: Option<Promise<LogoOperationResult>>,
+    set_logo_promise: Option<Promise<OperationResult>>,
+    restore_logo_promise: Option<Promise<OperationResult>>,
     // 添加待处理标志，用于在下一帧启动异步操作
     pending_set_logo: bool,
     pending_restore_logo: bool,
+    // 固件上次启动时显示的Logo（ACPI BGRT）
+    bgrt: Option<Result<Bgrt, String>>,
+    bgrt_texture: Option<egui::TextureHandle>,
+    bgrt_export_result: Option<Result<String, String>>,
+    // 上次开机是否显示了自定义Logo
+    confirmation: Option<Confirmation>,
+    // ESP中已安装的Logo，读取需要挂载ESP，由用户点击后在后台读取
+    installed_promise: Option<Promise<Result<Vec<InstalledLogo>, String>>>,
+    installed: Option<Result<Vec<InstalledLogo>, String>>,
+    installed_texture: Option<egui::TextureHandle>,
+    installed_export_result: Option<Result<String, String>>,
+    // 机型数据库中与本机匹配的机型，未测试的机型为None
+    device_profile: Option<&'static DeviceProfile>,
+    // 转换图片格式失败的原因
+    convert_error: Option<String>,
+    // 生成的兼容性报告（Markdown或JSON）
+    report_text: Option<String>,
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.is_admin {
-            self.show_main_ui(ctx);
-        } else {
-            self.show_admin_prompt_ui(ctx);
+        if let Some(boot_preview) = &mut self.boot_preview {
+            let lang = Lang::from_code(&self.language);
+            if !boot_preview.show(ctx, lang) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+                self.boot_preview = None;
+            }
+            return;
         }
+        self.show_main_ui(ctx);
     }
 }
 
 impl MyApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, logger: Option<&'static BufferLogger>) -> Self {
         log::debug!("Start MyApp::new");
         setup_custom_fonts(&cc.egui_ctx);
         let is_admin = NativePlatform::is_admin();
+        let can_elevate = !is_admin && helper::can_elevate();
         let mut platform_info = PlatformInfo::default();
         let mut is_support = false;
-        if is_admin {
-            is_support = platform_info.get_info();
+        let mut support_diagnosis = None;
+        // EFI变量对普通用户可读，非管理员也可以查看平台信息
+        match platform_info.get_info() {
+            Ok(()) => is_support = true,
+            Err(diag) => {
+                log::error!("Device not supported: {}", diag);
+                support_diagnosis = Some(diag);
+            }
         }
         let language = String::from("en");
         let is_loading_icon = NativePlatform::get_loading_icon();
         let set_loading_icon = is_loading_icon;
+        let bgrt = Bgrt::read();
+        if let Err(err) = &bgrt {
+            log::info!("BGRT not available: {}", err);
+        }
+        let confirmation = confirm::confirm();
+        log::info!("Boot confirmation: {}", confirmation);
+        let device_profile = profiles::current();
+        match device_profile {
+            Some(profile) => {
+                log::info!("Known good device: {}", profile.name);
+                for difference in profile.differences(&platform_info) {
+                    log::warn!(
+                        "{} differs from the device database: {}",
+                        profile.name,
+                        difference
+                    );
+                }
+            }
+            None => log::info!("Untested device"),
+        }
 
         Self {
             language,
             is_admin,
+            can_elevate,
             is_support,
+            support_diagnosis,
             is_loading_icon,
             set_loading_icon,
+            sanitize: true,
             platform_info,
+            bgrt: Some(bgrt),
+            confirmation: Some(confirmation),
+            device_profile,
+            log_panel: logger.map(LogPanel::new),
             ..Default::default()
         }
     }
@@ -211,8 +533,10 @@ impl MyApp {
             });
             ui.separator();
 
+            self.show_device_profile_ui(ui, lang);
             if self.is_support {
                 ui.colored_label(Color32::LIGHT_GREEN, t(lang, "supported"));
+                self.show_confirmation_ui(ui, lang);
 
                 ui.separator();
                 if self.platform_info.enable != 0 {
@@ -239,167 +563,20 @@ impl MyApp {
                     self.platform_info.version
                 ));
 
-                ui.separator();
-
-                if !self.platform_info.support.is_empty() {
-                    // 只在Windows平台显示加载图标选项
-                    #[cfg(target_os = "windows")]
-                    ui.checkbox(
-                        &mut self.set_loading_icon,
-                        t(lang, "show_windows_loading").as_ref(),
-                    );
-
-                    if ui.button(t(lang, "pick_image").as_ref()).clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Image", &*self.platform_info.support)
-                            .pick_file()
-                        {
-                            self.picked_path = Some(path.display().to_string());
-                        }
-                    }
-                }
-
-                if let Some(picked_path) = &self.picked_path {
-                    if self.platform_info.version == 0x20003
-                        || self.platform_info.version == 0x20000
-                    {
-                        ui.horizontal(|ui| {
-                            ui.label(t(lang, "picked_image").as_ref());
-                            ui.monospace(picked_path);
-                        });
-                        if ui
-                            .button(
-                                RichText::new(t(lang, "change_logo_btn").to_string())
-                                    .color(Color32::RED),
-                            )
-                            .clicked()
-                            && self.set_logo_promise.is_none()
-                            && !self.pending_set_logo
-                        {
-                            self.last_restore_logo = 0;
-                            self.last_set_logo = 0;
-                            // 标记为待处理，在下一帧启动异步操作
-                            self.pending_set_logo = true;
-                            ctx.request_repaint();
-                        }
-
-                        // 在单独的逻辑块中启动异步操作，避免在按钮点击时立即执行
-                        if self.pending_set_logo && self.set_logo_promise.is_none() {
-                            // 捕获需要的数据
-                            let img_path = picked_path.clone();
-                            let show_loading_icon = self.set_loading_icon;
-                            let lbldesp_var = self.platform_info.lbldesp_var;
-                            let lbldvc_var = self.platform_info.lbldvc_var;
-
-                            // 在后台线程执行操作
-                            self.set_logo_promise =
-                                Some(Promise::spawn_thread("set_logo", move || {
-                                    perform_set_logo_operation(
-                                        img_path,
-                                        show_loading_icon,
-                                        lbldesp_var,
-                                        lbldvc_var,
-                                    )
-                                }));
-                            self.pending_set_logo = false;
-                        }
-
-                        // 检查Promise是否完成
-                        if let Some(promise) = &self.set_logo_promise {
-                            if let Some(result) = promise.ready() {
-                                // 操作完成，更新状态（从后台线程返回的结果更新，不在UI线程读取）
-                                self.is_loading_icon = result.new_loading_icon_state;
-                                self.set_loading_icon = result.new_loading_icon_state;
-                                self.last_set_logo = if result.success { 1 } else { -1 };
-
-                                // 使用后台线程返回的平台信息，避免在UI线程调用get_info()
-                                self.platform_info.enable = result.enable;
-                                self.platform_info.lbldesp_var = result.lbldesp_var;
-                                self.platform_info.lbldvc_var = result.lbldvc_var;
-
-                                // 清除Promise
-                                self.set_logo_promise = None;
-                            } else {
-                                // 正在处理中，显示spinner
-                                ui.horizontal(|ui| {
-                                    ui.spinner();
-                                    ui.label(t(lang, "setting_logo_wait").as_ref());
-                                });
-                                ctx.request_repaint(); // 继续请求重绘以更新UI
-                            }
-                        }
-                    }
-                }
-
-                match self.last_set_logo {
-                    1 => {
-                        ui.colored_label(Color32::LIGHT_GREEN, t(lang, "change_logo_success"));
-                    }
-                    -1 => {
-                        ui.colored_label(Color32::LIGHT_RED, t(lang, "change_logo_failed"));
-                    }
-                    _ => {}
+                self.show_bgrt_ui(ui, lang);
+                self.show_installed_ui(ui, ctx, lang);
+                self.show_report_ui(ui, lang);
+                if let Some(log_panel) = &mut self.log_panel {
+                    log_panel.show(ui, lang);
                 }
 
                 ui.separator();
-                if ui.button(t(lang, "restore_logo_btn").as_ref()).clicked()
-                    && self.restore_logo_promise.is_none()
-                    && !self.pending_restore_logo
-                {
-                    self.last_restore_logo = 0;
-                    self.last_set_logo = 0;
-                    // 标记为待处理，在下一帧启动异步操作
-                    self.pending_restore_logo = true;
-                    ctx.request_repaint();
-                }
-
-                // 在单独的逻辑块中启动异步操作，避免在按钮点击时立即执行
-                if self.pending_restore_logo && self.restore_logo_promise.is_none() {
-                    // 捕获需要的数据
-                    let lbldesp_var = self.platform_info.lbldesp_var;
-                    let lbldvc_var = self.platform_info.lbldvc_var;
 
-                    // 在后台线程执行操作
-                    self.restore_logo_promise =
-                        Some(Promise::spawn_thread("restore_logo", move || {
-                            perform_restore_logo_operation(lbldesp_var, lbldvc_var)
-                        }));
-                    self.pending_restore_logo = false;
-                }
-
-                // 检查Promise是否完成
-                if let Some(promise) = &self.restore_logo_promise {
-                    if let Some(result) = promise.ready() {
-                        // 操作完成，更新状态（从后台线程返回的结果更新，不在UI线程读取）
-                        self.is_loading_icon = result.new_loading_icon_state;
-                        self.set_loading_icon = result.new_loading_icon_state;
-                        self.last_restore_logo = if result.success { 1 } else { -1 };
-
-                        // 使用后台线程返回的平台信息，避免在UI线程调用get_info()
-                        self.platform_info.enable = result.enable;
-                        self.platform_info.lbldesp_var = result.lbldesp_var;
-                        self.platform_info.lbldvc_var = result.lbldvc_var;
-                        self.is_support = result.enable != 0 || result.success;
-
-                        // 清除Promise
-                        self.restore_logo_promise = None;
-                    } else {
-                        // 正在处理中，显示spinner
-                        ui.horizontal(|ui| {
-                            ui.spinner();
-                            ui.label(t(lang, "restoring_logo_wait").as_ref());
-                        });
-                        ctx.request_repaint(); // 继续请求重绘以更新UI
-                    }
-                }
-                match self.last_restore_logo {
-                    1 => {
-                        ui.colored_label(Color32::LIGHT_GREEN, t(lang, "restore_logo_success"));
-                    }
-                    -1 => {
-                        ui.colored_label(Color32::LIGHT_RED, t(lang, "restore_logo_failed"));
-                    }
-                    _ => {}
+                if self.is_admin || self.can_elevate {
+                    self.show_actions_ui(ui, ctx, lang);
+                } else {
+                    // 读取信息不需要权限，修改和恢复才需要
+                    ui.colored_label(Color32::YELLOW, t(lang, "admin_required"));
                 }
 
                 ui.separator();
@@ -422,26 +599,964 @@ impl MyApp {
                                     "❓ Issue",
                                     "https://github.com/chnzzh/lenovo-logo-changer/issues",
                                 )
-                                .on_hover_text(
-                                    "Having problems? Open an issue on GitHub!",
-                                );
+                                .on_hover_text("Having problems? Open an issue on GitHub!");
                                 ui.label("Version 0.2.0 | MIT License | © 2026 chnzzh");
                             });
                         });
                     ui.add_space(4.0);
                 });
             } else {
-                ui.label(t(lang, "unsupported").as_ref());
+                self.show_unsupported_ui(ui, lang);
+                ui.separator();
+                self.show_report_ui(ui, lang);
+                if let Some(log_panel) = &mut self.log_panel {
+                    ui.separator();
+                    log_panel.show(ui, lang);
+                }
             }
         });
     }
 
-    fn show_admin_prompt_ui(&mut self, ctx: &egui::Context) {
-        let lang = Lang::from_code(&self.language);
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.label(t(lang, "admin_required").as_ref());
+    fn show_actions_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, lang: Lang) {
+        if !self.platform_info.support.is_empty() {
+            // 只在Windows平台显示加载图标选项
+            #[cfg(target_os = "windows")]
+            ui.checkbox(
+                &mut self.set_loading_icon,
+                t(lang, "show_windows_loading").as_ref(),
+            );
+
+            ui.checkbox(&mut self.sanitize, t(lang, "sanitize").as_ref())
+                .on_hover_text(t(lang, "sanitize_hint"));
+
+            if ui.button(t(lang, "pick_image").as_ref()).clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Image", &self.platform_info.support)
+                    .pick_file()
+            {
+                let path = path.display().to_string();
+                self.preview = Some(Preview::load(ctx, &path, &self.platform_info));
+                self.picked_path = Some(path);
+                self.convert_error = None;
+            }
+
+            // 把固件不支持的图片转换为支持的格式，转换结果作为选中的图片
+            if ui
+                .button(t(lang, "convert_image").as_ref())
+                .on_hover_text(t(lang, "convert_image_hint"))
+                .clicked()
+            {
+                match self.convert_image() {
+                    Ok(Some(path)) => {
+                        self.preview = Some(Preview::load(ctx, &path, &self.platform_info));
+                        self.picked_path = Some(path);
+                        self.convert_error = None;
+                    }
+                    Ok(None) => {}
+                    Err(err) => self.convert_error = Some(err),
+                }
+            }
+            if let Some(err) = &self.convert_error {
+                ui.colored_label(Color32::LIGHT_RED, err);
+            }
+        }
+
+        if let Some(picked_path) = self.picked_path.clone()
+            && (self.platform_info.version == 0x20003 || self.platform_info.version == 0x20000)
+        {
+            ui.horizontal(|ui| {
+                ui.label(t(lang, "picked_image").as_ref());
+                ui.monospace(&picked_path);
+            });
+            let action = match &mut self.preview {
+                Some(preview) => show_preview_ui(ui, lang, preview, &self.platform_info),
+                None => PreviewAction::None,
+            };
+            match action {
+                PreviewAction::None => {}
+                PreviewAction::BootPreview => {
+                    // Linux下没有加载图标，只有Windows根据选项显示
+                    let spinner = cfg!(target_os = "windows") && self.set_loading_icon;
+                    match BootPreview::load(ctx, &picked_path, self.sanitize, spinner) {
+                        Ok(boot_preview) => {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+                            self.boot_preview = Some(boot_preview);
+                        }
+                        Err(err) => log::error!("Boot preview failed: {}", err),
+                    }
+                }
+                PreviewAction::FlattenGif => match self.flatten_gif(&picked_path) {
+                    Ok(Some(path)) => {
+                        self.preview = Some(Preview::load(ctx, &path, &self.platform_info));
+                        self.picked_path = Some(path);
+                        self.convert_error = None;
+                    }
+                    Ok(None) => {}
+                    Err(err) => self.convert_error = Some(err),
+                },
+            }
+            let can_change = self.preview.as_ref().is_none_or(Preview::can_change);
+            if ui
+                .add_enabled(
+                    can_change,
+                    egui::Button::new(
+                        RichText::new(t(lang, "change_logo_btn").to_string()).color(Color32::RED),
+                    ),
+                )
+                .clicked()
+                && self.set_logo_promise.is_none()
+                && self.set_logo_plan_promise.is_none()
+                && !self.pending_set_logo
+            {
+                // 先显示将要做的修改，用户确认后才写入；处理图片和读取ESP在后台线程执行
+                let img_path = picked_path.clone();
+                let (sanitize, show_loading_icon) = (self.sanitize, self.set_loading_icon);
+                let platform_info = self.platform_info.clone();
+                let use_helper = !self.is_admin;
+                self.set_logo_plan_promise =
+                    Some(Promise::spawn_thread("plan_set_logo", move || {
+                        operations::plan_set_logo(
+                            &img_path,
+                            sanitize,
+                            show_loading_icon,
+                            &platform_info,
+                            use_helper,
+                        )
+                    }));
+            }
+            if let Some(promise) = self.set_logo_plan_promise.take() {
+                // 取出结果，避免复制图片数据
+                match promise.try_take() {
+                    Ok(Ok(plan)) => {
+                        self.set_logo_plan = Some(plan);
+                        self.set_logo_plan_checked = false;
+                        self.set_logo_plan_error = None;
+                    }
+                    Ok(Err(err)) => self.set_logo_plan_error = Some(err),
+                    Err(promise) => {
+                        self.set_logo_plan_promise = Some(promise);
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(t(lang, "installed_reading"));
+                        });
+                        ctx.request_repaint();
+                    }
+                }
+            }
+            if let Some(err) = &self.set_logo_plan_error {
+                ui.colored_label(Color32::LIGHT_RED, err);
+            }
+            if self.show_set_logo_plan_ui(ctx, lang) {
+                self.last_restore_logo = 0;
+                self.last_set_logo = 0;
+                self.confirmed_plan = self.set_logo_plan.take();
+                // 标记为待处理，在下一帧启动异步操作
+                self.pending_set_logo = true;
+                ctx.request_repaint();
+            }
+
+            // 在单独的逻辑块中启动异步操作，避免在按钮点击时立即执行
+            if self.pending_set_logo && self.set_logo_promise.is_none() {
+                // 写入确认对话框中显示的数据，而不是重新读取文件
+                if let Some(plan) = self.confirmed_plan.take() {
+                    let show_loading_icon = self.set_loading_icon;
+                    let platform_info = self.platform_info.clone();
+                    let use_helper = !self.is_admin;
+
+                    // 后台线程通过channel报告每个步骤的进度
+                    let (sender, receiver) = mpsc::channel();
+                    self.progress_receiver = Some(receiver);
+                    self.progress_steps = [None; Step::ALL.len()];
+
+                    // 在后台线程执行操作
+                    self.set_logo_promise = Some(Promise::spawn_thread("set_logo", move || {
+                        progress::with_sender(sender, || {
+                            operations::set_logo_data(
+                                plan.data,
+                                &plan.ext,
+                                show_loading_icon,
+                                platform_info,
+                                use_helper,
+                            )
+                        })
+                    }));
+                }
+                self.pending_set_logo = false;
+            }
+
+            // 接收后台线程发来的进度事件
+            if let Some(receiver) = &self.progress_receiver {
+                for event in receiver.try_iter() {
+                    if let Some(index) = Step::ALL.iter().position(|step| *step == event.step) {
+                        self.progress_steps[index] = Some(event.status);
+                    }
+                }
+            }
+
+            // 检查Promise是否完成
+            if let Some(promise) = &self.set_logo_promise {
+                if let Some(result) = promise.ready() {
+                    // 操作完成，更新状态（从后台线程返回的结果更新，不在UI线程读取）
+                    self.is_loading_icon = result.new_loading_icon_state;
+                    self.set_loading_icon = result.new_loading_icon_state;
+                    self.last_set_logo = if result.success { 1 } else { -1 };
+
+                    // 使用后台线程返回的平台信息，避免在UI线程调用get_info()
+                    self.platform_info = result.platform_info.clone();
+
+                    // 清除Promise，发送端已随线程结束关闭
+                    self.set_logo_promise = None;
+                    self.progress_receiver = None;
+                } else {
+                    // 正在处理中，显示spinner
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(t(lang, "setting_logo_wait").as_ref());
+                    });
+                    ctx.request_repaint(); // 继续请求重绘以更新UI
+                }
+            }
+            self.show_progress_ui(ui, lang);
+        }
+
+        match self.last_set_logo {
+            1 => {
+                ui.colored_label(Color32::LIGHT_GREEN, t(lang, "change_logo_success"));
+            }
+            -1 => {
+                ui.colored_label(Color32::LIGHT_RED, t(lang, "change_logo_failed"));
+            }
+            _ => {}
+        }
+
+        ui.separator();
+        if ui.button(t(lang, "restore_logo_btn").as_ref()).clicked()
+            && self.restore_logo_promise.is_none()
+            && !self.pending_restore_logo
+        {
+            self.last_restore_logo = 0;
+            self.last_set_logo = 0;
+            // 标记为待处理，在下一帧启动异步操作
+            self.pending_restore_logo = true;
+            ctx.request_repaint();
+        }
+
+        // 在单独的逻辑块中启动异步操作，避免在按钮点击时立即执行
+        if self.pending_restore_logo && self.restore_logo_promise.is_none() {
+            // 捕获需要的数据
+            let platform_info = self.platform_info.clone();
+            let use_helper = !self.is_admin;
+
+            // 在后台线程执行操作
+            self.restore_logo_promise = Some(Promise::spawn_thread("restore_logo", move || {
+                operations::restore_logo(platform_info, use_helper)
+            }));
+            self.pending_restore_logo = false;
+        }
+
+        // 检查Promise是否完成
+        if let Some(promise) = &self.restore_logo_promise {
+            if let Some(result) = promise.ready() {
+                // 操作完成，更新状态（从后台线程返回的结果更新，不在UI线程读取）
+                self.is_loading_icon = result.new_loading_icon_state;
+                self.set_loading_icon = result.new_loading_icon_state;
+                self.last_restore_logo = if result.success { 1 } else { -1 };
+
+                // 使用后台线程返回的平台信息，避免在UI线程调用get_info()
+                self.platform_info = result.platform_info.clone();
+                self.is_support = result.support.is_ok();
+                self.support_diagnosis = result.support.clone().err();
+
+                // 清除Promise
+                self.restore_logo_promise = None;
+            } else {
+                // 正在处理中，显示spinner
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(t(lang, "restoring_logo_wait").as_ref());
+                });
+                ctx.request_repaint(); // 继续请求重绘以更新UI
+            }
+        }
+        match self.last_restore_logo {
+            1 => {
+                ui.colored_label(Color32::LIGHT_GREEN, t(lang, "restore_logo_success"));
+            }
+            -1 => {
+                ui.colored_label(Color32::LIGHT_RED, t(lang, "restore_logo_failed"));
+            }
+            _ => {}
+        }
+    }
+
+    /// 显示设置Logo各步骤的状态和耗时，没有开始过设置时不显示
+    fn show_progress_ui(&self, ui: &mut egui::Ui, lang: Lang) {
+        if self.progress_steps.iter().all(Option::is_none) {
+            return;
+        }
+        egui::Grid::new("set_logo_progress")
+            .num_columns(3)
+            .spacing([8.0, 2.0])
+            .show(ui, |ui| {
+                for (step, status) in Step::ALL.iter().zip(&self.progress_steps) {
+                    let label = t(lang, &format!("step_{}", step.code()));
+                    match status {
+                        None => {
+                            ui.label(RichText::new("○").color(Color32::GRAY));
+                            ui.label(RichText::new(label).color(Color32::GRAY));
+                            ui.label("");
+                        }
+                        Some(StepStatus::Running) => {
+                            ui.spinner();
+                            ui.label(label);
+                            ui.label("");
+                        }
+                        Some(StepStatus::Done(elapsed)) => {
+                            ui.colored_label(Color32::LIGHT_GREEN, "✔");
+                            ui.label(label);
+                            ui.small(format!("{} ms", elapsed.as_millis()));
+                        }
+                        Some(StepStatus::Failed(elapsed)) => {
+                            ui.colored_label(Color32::LIGHT_RED, "✘");
+                            ui.colored_label(Color32::LIGHT_RED, label);
+                            ui.small(format!("{} ms", elapsed.as_millis()));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// 显示设置Logo的确认对话框，返回用户是否确认
+    fn show_set_logo_plan_ui(&mut self, ctx: &egui::Context, lang: Lang) -> bool {
+        let Some(plan) = &self.set_logo_plan else {
+            return false;
+        };
+        let mut confirmed = false;
+        let mut cancelled = false;
+        let modal = egui::Modal::new(egui::Id::new("set_logo_plan")).show(ctx, |ui| {
+            ui.set_max_width(560.0);
+            ui.heading(t(lang, "plan_title"));
+            ui.separator();
+
+            egui::Grid::new("set_logo_plan_grid")
+                .num_columns(2)
+                .spacing([12.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label(t(lang, "plan_source"));
+                    ui.vertical(|ui| {
+                        ui.monospace(&plan.source);
+                        ui.label(format!("{} bytes", plan.data.len()));
+                        ui.monospace(format!("sha256 {}", plan.sha256));
+                    });
+                    ui.end_row();
+
+                    ui.label(t(lang, "plan_destination"));
+                    ui.monospace(&plan.destination);
+                    ui.end_row();
+
+                    ui.label(t(lang, "plan_deleted"));
+                    ui.vertical(|ui| match &plan.deleted {
+                        Some(files) if files.is_empty() => {
+                            ui.label(t(lang, "plan_deleted_none"));
+                        }
+                        Some(files) => {
+                            for file in files {
+                                ui.colored_label(
+                                    Color32::LIGHT_RED,
+                                    RichText::new(file).monospace(),
+                                );
+                            }
+                        }
+                        None => {
+                            ui.label(t(lang, "plan_deleted_unknown"));
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("LBLDESP");
+                    ui.vertical(|ui| {
+                        ui.label(hex_diff(&plan.lbldesp_old, &plan.lbldesp_new, false));
+                        ui.label(hex_diff(&plan.lbldesp_old, &plan.lbldesp_new, true));
+                    });
+                    ui.end_row();
+
+                    ui.label("LBLDVC");
+                    ui.vertical(|ui| {
+                        ui.label(hex_diff(&plan.lbldvc_old, &plan.lbldvc_new, false));
+                        ui.label(hex_diff(&plan.lbldvc_old, &plan.lbldvc_new, true));
+                    });
+                    ui.end_row();
+
+                    if let Some((_, show)) = plan.loading_icon {
+                        ui.label(t(lang, "show_windows_loading"));
+                        ui.colored_label(
+                            Color32::YELLOW,
+                            t(
+                                lang,
+                                if show {
+                                    "plan_icon_show"
+                                } else {
+                                    "plan_icon_hide"
+                                },
+                            ),
+                        );
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            ui.checkbox(
+                &mut self.set_logo_plan_checked,
+                t(lang, "plan_checkbox").as_ref(),
+            );
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        self.set_logo_plan_checked,
+                        egui::Button::new(
+                            RichText::new(t(lang, "plan_confirm")).color(Color32::RED),
+                        ),
+                    )
+                    .clicked()
+                {
+                    confirmed = true;
+                }
+                if ui.button(t(lang, "plan_cancel").as_ref()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+        if confirmed {
+            return true;
+        }
+        if cancelled || modal.should_close() {
+            self.set_logo_plan = None;
+        }
+        false
+    }
+
+    fn show_confirmation_ui(&self, ui: &mut egui::Ui, lang: Lang) {
+        let percent = |similarity: &f32| format!("{:.0}", similarity * 100.0);
+        match &self.confirmation {
+            Some(Confirmation::Confirmed { similarity }) => {
+                ui.colored_label(
+                    Color32::LIGHT_GREEN,
+                    RichText::new(
+                        t(lang, "confirm_confirmed").replace("{similarity}", &percent(similarity)),
+                    )
+                    .strong(),
+                );
+            }
+            Some(Confirmation::Rejected { similarity }) => {
+                ui.colored_label(
+                    Color32::LIGHT_RED,
+                    RichText::new(
+                        t(lang, "confirm_rejected").replace("{similarity}", &percent(similarity)),
+                    )
+                    .strong(),
+                );
+            }
+            Some(Confirmation::NotRebooted) => {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    RichText::new(t(lang, "confirm_not_rebooted")).strong(),
+                );
+            }
+            Some(Confirmation::Unknown(reason)) => {
+                ui.small(t(lang, "confirm_unknown").replace("{error}", reason));
+            }
+            Some(Confirmation::NothingRecorded) | None => {}
+        }
+    }
+
+    fn show_bgrt_ui(&mut self, ui: &mut egui::Ui, lang: Lang) {
+        ui.collapsing(t(lang, "bgrt_title"), |ui| {
+            let bgrt = match &self.bgrt {
+                Some(Ok(bgrt)) => bgrt,
+                Some(Err(err)) => {
+                    ui.label(t(lang, "bgrt_unavailable").replace("{error}", err));
+                    return;
+                }
+                None => return,
+            };
+
+            let (width, height) = bgrt.dimensions();
+            ui.label(t(lang, "bgrt_desc"));
+            ui.label(format!(
+                "{}x{} @ ({}, {})",
+                width, height, bgrt.xoffset, bgrt.yoffset
+            ));
+
+            let texture = self.bgrt_texture.get_or_insert_with(|| {
+                let image = egui::ColorImage::from_rgba_unmultiplied(
+                    [width as usize, height as usize],
+                    bgrt.image.as_raw(),
+                );
+                ui.ctx().load_texture("bgrt", image, Default::default())
+            });
+            ui.add(egui::Image::new(&*texture).max_size(egui::vec2(240.0, 160.0)));
+
+            if ui.button(t(lang, "bgrt_export").as_ref()).clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("BMP", &["bmp"])
+                    .add_filter("PNG", &["png"])
+                    .set_file_name("bgrt_logo.bmp")
+                    .save_file()
+            {
+                self.bgrt_export_result =
+                    Some(bgrt.export(&path).map(|()| path.display().to_string()));
+            }
+            match &self.bgrt_export_result {
+                Some(Ok(path)) => {
+                    ui.colored_label(
+                        Color32::LIGHT_GREEN,
+                        t(lang, "bgrt_exported").replace("{path}", path),
+                    );
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(Color32::LIGHT_RED, err);
+                }
+                None => {}
+            }
+        });
+    }
+
+    fn show_report_ui(&mut self, ui: &mut egui::Ui, lang: Lang) {
+        ui.collapsing(t(lang, "report_title"), |ui| {
+            ui.label(t(lang, "report_desc"));
+            ui.horizontal(|ui| {
+                let markdown = ui.button(t(lang, "report_markdown").as_ref()).clicked();
+                let json = ui.button(t(lang, "report_json").as_ref()).clicked();
+                if markdown || json {
+                    let report = CompatReport::collect(self.last_operation());
+                    self.report_text = Some(if json {
+                        report.to_json()
+                    } else {
+                        report.to_markdown()
+                    });
+                }
+            });
+
+            let Some(text) = &mut self.report_text else {
+                return;
+            };
+            if ui.button(t(lang, "report_copy").as_ref()).clicked() {
+                ui.ctx().copy_text(text.clone());
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("report")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut text.as_str())
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        });
+    }
+
+    /// 本次运行中上次设置/恢复的结果，失败时附上失败的步骤
+    fn last_operation(&self) -> Option<String> {
+        let failed_step = Step::ALL
+            .iter()
+            .zip(&self.progress_steps)
+            .find(|(_, status)| matches!(status, Some(StepStatus::Failed(_))))
+            .map(|(step, _)| step);
+        match (self.last_set_logo, self.last_restore_logo) {
+            (1, _) => Some("set logo: success".to_string()),
+            (-1, _) => Some(match failed_step {
+                Some(step) => format!("set logo: failed at \"{}\"", step),
+                None => "set logo: failed".to_string(),
+            }),
+            (_, 1) => Some("restore logo: success".to_string()),
+            (_, -1) => Some("restore logo: failed".to_string()),
+            _ => None,
+        }
+    }
+
+    fn show_installed_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, lang: Lang) {
+        ui.collapsing(t(lang, "installed_title"), |ui| {
+            if !self.is_admin && !self.can_elevate {
+                ui.colored_label(Color32::YELLOW, t(lang, "admin_required"));
+                return;
+            }
+
+            if let Some(promise) = &self.installed_promise {
+                if let Some(result) = promise.ready() {
+                    self.installed = Some(result.clone());
+                    self.installed_texture = None;
+                    self.installed_export_result = None;
+                    self.installed_promise = None;
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(t(lang, "installed_reading"));
+                    });
+                    ctx.request_repaint();
+                    return;
+                }
+            }
+
+            if ui.button(t(lang, "installed_read").as_ref()).clicked() {
+                let platform_info = self.platform_info.clone();
+                let use_helper = !self.is_admin;
+                self.installed_promise = Some(Promise::spawn_thread("read_installed", move || {
+                    operations::read_installed_logo(&platform_info, use_helper)
+                }));
+            }
+
+            let logos = match &self.installed {
+                Some(Ok(logos)) => logos,
+                Some(Err(err)) => {
+                    ui.colored_label(Color32::LIGHT_RED, err);
+                    return;
+                }
+                None => return,
+            };
+            if logos.is_empty() {
+                ui.label(t(lang, "installed_empty"));
+                return;
+            }
+            for logo in logos {
+                ui.horizontal(|ui| {
+                    ui.monospace(&logo.name);
+                    ui.label(format!("{} bytes", logo.data.len()));
+                    ui.monospace(&logo.sha256[..16]).on_hover_text(&logo.sha256);
+                    if logo.matches_lbldvc {
+                        ui.colored_label(Color32::LIGHT_GREEN, t(lang, "installed_matches"));
+                    } else {
+                        ui.colored_label(Color32::LIGHT_RED, t(lang, "installed_mismatch"));
+                    }
+                });
+            }
+
+            let Some(active) = InstalledLogo::active(logos) else {
+                return;
+            };
+            if self.installed_texture.is_none()
+                && let Ok(img) = imaging::decode(&active.data, &active.ext())
+            {
+                let img = img
+                    .thumbnail(PREVIEW_TEXTURE_MAX, PREVIEW_TEXTURE_MAX)
+                    .to_rgba8();
+                let image = egui::ColorImage::from_rgba_unmultiplied(
+                    [img.width() as usize, img.height() as usize],
+                    img.as_raw(),
+                );
+                self.installed_texture = Some(ui.ctx().load_texture(
+                    "installed",
+                    image,
+                    Default::default(),
+                ));
+            }
+            if let Some(texture) = &self.installed_texture {
+                ui.add(egui::Image::new(texture).max_size(egui::vec2(240.0, 160.0)));
+            }
+
+            if ui.button(t(lang, "installed_export").as_ref()).clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Image", &[active.ext().as_str(), "png"])
+                    .set_file_name(&active.name)
+                    .save_file()
+            {
+                self.installed_export_result =
+                    Some(active.export(&path).map(|()| path.display().to_string()));
+            }
+            match &self.installed_export_result {
+                Some(Ok(path)) => {
+                    ui.colored_label(
+                        Color32::LIGHT_GREEN,
+                        t(lang, "bgrt_exported").replace("{path}", path),
+                    );
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(Color32::LIGHT_RED, err);
+                }
+                None => {}
+            }
         });
     }
+
+    /// 选择任意图片并保存为固件支持的格式，返回转换后的路径，用户取消时返回None
+    fn convert_image(&self) -> Result<Option<String>, String> {
+        let Some(source) = rfd::FileDialog::new()
+            .add_filter("Image", &CONVERT_SOURCE_FORMATS)
+            .pick_file()
+        else {
+            return Ok(None);
+        };
+        let stem = source
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("logo");
+        let mut dialog = rfd::FileDialog::new()
+            .set_file_name(format!("{}.{}", stem, self.platform_info.support[0]));
+        for format in &self.platform_info.support {
+            dialog = dialog.add_filter(format.to_uppercase(), &[*format]);
+        }
+        let Some(target) = dialog.save_file() else {
+            return Ok(None);
+        };
+
+        let ext_of = |path: &std::path::Path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+        };
+        let target_ext = ext_of(&target);
+        if !self.platform_info.support.contains(&target_ext.as_str()) {
+            return Err(format!(
+                "format {} is not supported by the firmware",
+                target_ext
+            ));
+        }
+        let data =
+            std::fs::read(&source).map_err(|err| format!("read {}: {}", source.display(), err))?;
+        let converted = imaging::convert(&data, &ext_of(&source), &target_ext)?;
+        std::fs::write(&target, converted)
+            .map_err(|err| format!("write {}: {}", target.display(), err))?;
+        Ok(Some(target.display().to_string()))
+    }
+
+    /// 把选中的多帧GIF压平为预览中选中的帧并另存，返回新文件的路径，用户取消时返回None
+    fn flatten_gif(&self, picked_path: &str) -> Result<Option<String>, String> {
+        let Some(preview) = &self.preview else {
+            return Ok(None);
+        };
+        let Some(data) = &preview.gif_data else {
+            return Ok(None);
+        };
+        let stem = std::path::Path::new(picked_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("logo");
+        let Some(target) = rfd::FileDialog::new()
+            .add_filter("GIF", &["gif"])
+            .set_file_name(format!("{}_frame{}.gif", stem, preview.frame))
+            .save_file()
+        else {
+            return Ok(None);
+        };
+        let flattened = imaging::flatten_gif(data, preview.frame)?;
+        std::fs::write(&target, flattened)
+            .map_err(|err| format!("write {}: {}", target.display(), err))?;
+        Ok(Some(target.display().to_string()))
+    }
+
+    fn show_device_profile_ui(&self, ui: &mut egui::Ui, lang: Lang) {
+        match self.device_profile {
+            Some(profile) => {
+                ui.colored_label(
+                    Color32::LIGHT_GREEN,
+                    t(lang, "profile_known").replace("{device}", &profile.name),
+                );
+                if !profile.verified_formats.is_empty() {
+                    ui.small(
+                        t(lang, "profile_verified_formats")
+                            .replace("{formats}", &profile.verified_formats.join(" / ")),
+                    );
+                }
+                if !profile.broken_formats.is_empty() {
+                    ui.small(
+                        t(lang, "profile_broken_formats")
+                            .replace("{formats}", &profile.broken_formats.join(" / ")),
+                    );
+                }
+            }
+            None => {
+                ui.colored_label(Color32::YELLOW, t(lang, "profile_untested"));
+            }
+        }
+    }
+
+    fn show_unsupported_ui(&self, ui: &mut egui::Ui, lang: Lang) {
+        ui.colored_label(Color32::LIGHT_RED, t(lang, "unsupported"));
+        if let Some(diag) = &self.support_diagnosis {
+            let key = diag.i18n_key();
+            ui.separator();
+            ui.label(diag.fill(&t(lang, key)));
+            ui.add_space(8.0);
+            ui.label(RichText::new(t(lang, "next_steps")).strong());
+            ui.label(diag.fill(&t(lang, &format!("{}_hint", key))));
+        }
+    }
+}
+
+/// 显示图片预览：黑色区域为固件最大Logo区域，图片按固件的方式居中显示
+///
+/// 返回是否点击了"预览开机画面"
+fn show_preview_ui(
+    ui: &mut egui::Ui,
+    lang: Lang,
+    preview: &mut Preview,
+    platform_info: &PlatformInfo,
+) -> PreviewAction {
+    let (max_width, max_height) = (platform_info.width, platform_info.height);
+    let mut action = PreviewAction::None;
+    ui.horizontal(|ui| {
+        if let Some((width, height)) = preview.check.dimensions
+            && max_width > 0
+            && max_height > 0
+        {
+            // 同时容纳最大区域和图片
+            let scene = egui::vec2(max_width.max(width) as f32, max_height.max(height) as f32);
+            let scale = (PREVIEW_SIZE.x / scene.x).min(PREVIEW_SIZE.y / scene.y);
+            let (rect, _) = ui.allocate_exact_size(PREVIEW_SIZE, egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 0.0, Color32::from_gray(48));
+
+            let area = egui::Rect::from_center_size(
+                rect.center(),
+                egui::vec2(max_width as f32, max_height as f32) * scale,
+            );
+            painter.rect_filled(area, 0.0, Color32::BLACK);
+            if let Some(texture) = &preview.texture {
+                let image_rect = egui::Rect::from_center_size(
+                    rect.center(),
+                    egui::vec2(width as f32, height as f32) * scale,
+                );
+                painter.image(
+                    texture.id(),
+                    image_rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+            }
+            // 超出最大区域时边框显示为红色
+            let fits = width <= max_width && height <= max_height;
+            let color = if fits {
+                Color32::LIGHT_GREEN
+            } else {
+                Color32::LIGHT_RED
+            };
+            painter.rect_stroke(
+                area,
+                0.0,
+                egui::Stroke::new(1.5, color),
+                egui::StrokeKind::Outside,
+            );
+        }
+
+        ui.vertical(|ui| {
+            if let Some((width, height)) = preview.check.dimensions {
+                ui.label(format!(
+                    "{} : {}x{}",
+                    t(lang, "preview_image_size"),
+                    width,
+                    height
+                ));
+            }
+            ui.label(format!(
+                "{} : {}x{}",
+                t(lang, "max_image_size"),
+                max_width,
+                max_height
+            ));
+            if preview.texture.is_none() {
+                ui.small(t(lang, "preview_unavailable"));
+            }
+
+            let mut frames = 0;
+            for warning in &preview.check.warnings {
+                let color = if warning.is_blocking() {
+                    Color32::LIGHT_RED
+                } else {
+                    Color32::YELLOW
+                };
+                ui.colored_label(color, warning_text(lang, warning));
+                if let ImageWarning::Animated { frames: count } = warning {
+                    frames = *count;
+                }
+            }
+            if frames > 1 {
+                let mut frame = preview.frame;
+                ui.horizontal(|ui| {
+                    ui.label(t(lang, "preview_frame").as_ref());
+                    ui.add(egui::Slider::new(&mut frame, 0..=frames - 1));
+                });
+                if frame != preview.frame {
+                    preview.show_frame(ui.ctx(), frame);
+                }
+                if ui
+                    .button(t(lang, "preview_flatten").as_ref())
+                    .on_hover_text(t(lang, "preview_flatten_hint"))
+                    .clicked()
+                {
+                    action = PreviewAction::FlattenGif;
+                }
+                if !preview.check.is_blocked() {
+                    ui.checkbox(
+                        &mut preview.animated_confirmed,
+                        t(lang, "preview_animated_confirm").as_ref(),
+                    );
+                }
+            }
+
+            if ui
+                .add_enabled(
+                    preview.texture.is_some() && !preview.check.is_blocked(),
+                    egui::Button::new(t(lang, "boot_preview_btn").as_ref()),
+                )
+                .clicked()
+            {
+                action = PreviewAction::BootPreview;
+            }
+        });
+    });
+    action
+}
+
+/// 十六进制显示变量内容，与另一版本不同的字节高亮
+///
+/// `show_new` 为true时显示新内容（绿色高亮），否则显示旧内容（红色高亮），每行20字节
+fn hex_diff(old: &[u8], new: &[u8], show_new: bool) -> egui::text::LayoutJob {
+    let (prefix, bytes, highlight) = if show_new {
+        ("+ ", new, Color32::LIGHT_GREEN)
+    } else {
+        ("- ", old, Color32::LIGHT_RED)
+    };
+    let font = FontId::monospace(12.0);
+    let format = |color| egui::TextFormat::simple(font.clone(), color);
+    let mut job = egui::text::LayoutJob::default();
+    job.append(prefix, 0.0, format(Color32::GRAY));
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 && i % HEX_DIFF_LINE_BYTES == 0 {
+            job.append("\n  ", 0.0, format(Color32::GRAY));
+        }
+        let changed = old.get(i) != new.get(i);
+        job.append(
+            &format!("{:02x}", byte),
+            0.0,
+            format(if changed { highlight } else { Color32::GRAY }),
+        );
+    }
+    job
+}
+
+fn warning_text(lang: Lang, warning: &ImageWarning) -> String {
+    match warning {
+        ImageWarning::UnsupportedFormat(ext) => {
+            t(lang, "warn_unsupported_format").replace("{format}", ext)
+        }
+        ImageWarning::Unreadable(err) => t(lang, "warn_unreadable").replace("{error}", err),
+        ImageWarning::TooLarge {
+            width,
+            height,
+            max_width,
+            max_height,
+        } => t(lang, "warn_too_large")
+            .replace("{size}", &format!("{}x{}", width, height))
+            .replace("{max}", &format!("{}x{}", max_width, max_height)),
+        ImageWarning::Animated { frames } => {
+            t(lang, "warn_animated").replace("{frames}", &frames.to_string())
+        }
+        ImageWarning::BrokenOnDevice { format, device } => t(lang, "warn_broken_on_device")
+            .replace("{format}", format)
+            .replace("{device}", device),
+    }
 }
 
 fn setup_custom_fonts(ctx: &egui::Context) {
@@ -0,0 +1,405 @@
+//! Logo操作模块
+//!
+//! 组合加载图标、ESP和EFI变量的操作，供界面和命令行调用
+
+use log::{error, info, warn};
+use sha2::{Digest, Sha256};
+
+use crate::edk2_bmp;
+use crate::helper::HelperClient;
+use crate::imaging;
+use crate::installed::InstalledLogo;
+use crate::lenlogo::{LOGO_DIR, PlatformInfo, SupportDiagnosis, VerifyStatus};
+use crate::platform::{NativePlatform, PlatformOps};
+use crate::profiles::{self, DeviceProfile};
+use crate::progress::{self, Step};
+use crate::state;
+
+/// 设置/恢复Logo操作的结果
+pub struct OperationResult {
+    /// 操作是否成功
+    pub success: bool,
+    /// 操作后Windows加载图标的状态
+    pub new_loading_icon_state: bool,
+    /// 操作后重新读取的平台信息
+    pub platform_info: PlatformInfo,
+    /// 重新读取平台信息的结果
+    pub support: Result<(), SupportDiagnosis>,
+}
+
+/// 通过特权辅助进程执行操作（Linux下以普通用户运行时）
+fn perform_helper_operation(
+    op: impl FnOnce(&mut HelperClient) -> Result<(), String>,
+) -> OperationResult {
+    let mut client = HelperClient::spawn();
+    let success = match client.as_mut().map_err(|err| err.clone()).and_then(op) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("Helper operation failed: {}", err);
+            false
+        }
+    };
+
+    // 优先使用辅助进程读取的变量，失败时在本进程中读取（读取不需要权限）
+    let refreshed = client
+        .as_mut()
+        .ok()
+        .and_then(|client| match client.get_info() {
+            Ok(info) => Some(info),
+            Err(err) => {
+                warn!("Helper get-info failed: {}", err);
+                None
+            }
+        });
+    let (platform_info, support) = match refreshed {
+        Some(info) => (info, Ok(())),
+        None => {
+            let mut info = PlatformInfo::default();
+            let support = info.get_info();
+            (info, support)
+        }
+    };
+
+    OperationResult {
+        success,
+        new_loading_icon_state: NativePlatform::get_loading_icon(),
+        platform_info,
+        support,
+    }
+}
+
+/// 设置Logo前的变更摘要，供确认对话框显示
+pub struct SetLogoPlan {
+    /// 源文件路径
+    pub source: String,
+    /// 图片扩展名（小写）
+    pub ext: String,
+    /// 将写入ESP的图片数据
+    pub data: Vec<u8>,
+    /// 图片数据的SHA-256（hex）
+    pub sha256: String,
+    /// ESP中的目标路径
+    pub destination: String,
+    /// 将被删除的文件，`None` 表示没有读取ESP（Logo目录中的所有文件都会被删除）
+    pub deleted: Option<Vec<String>>,
+    /// 当前的LBLDESP
+    pub lbldesp_old: [u8; 10],
+    /// 写入的LBLDESP
+    pub lbldesp_new: [u8; 10],
+    /// 当前的LBLDVC
+    pub lbldvc_old: [u8; 40],
+    /// 写入的LBLDVC
+    pub lbldvc_new: [u8; 40],
+    /// 加载图标的变化（当前，设置后），不变或不支持时为None
+    pub loading_icon: Option<(bool, bool)>,
+}
+
+/// 读取图片并计算设置Logo将要做的修改，不写入任何内容
+///
+/// # 参数
+/// * `img_path` - 图片路径
+/// * `sanitize` - 是否完全重新生成图片（清洗模式），见 [`imaging::sanitize`]
+/// * `show_loading_icon` - 是否显示Windows加载图标
+/// * `platform_info` - 读取到的平台信息
+/// * `use_helper` - 写入是否通过特权辅助进程执行，此时不读取ESP中的Logo文件
+pub fn plan_set_logo(
+    img_path: &str,
+    sanitize: bool,
+    show_loading_icon: bool,
+    platform_info: &PlatformInfo,
+    use_helper: bool,
+) -> Result<SetLogoPlan, String> {
+    let (ext, data) = prepare_image(img_path, sanitize)?;
+    let lbldvc_new = platform_info
+        .checksum_var(&data)
+        .ok_or_else(|| format!("unsupported version: 0x{:x}", platform_info.version))?;
+    // 读取失败或跳过时不影响设置，对话框中提示整个Logo目录都会被删除。
+    // 没有权限时读取需要单独启动辅助进程，会比写入多弹出一次pkexec认证，因此跳过
+    let deleted = if use_helper {
+        info!("Skip listing the installed logo, it needs elevated privileges");
+        None
+    } else {
+        match read_installed_logo(platform_info, false) {
+            Ok(logos) => Some(logos.iter().map(InstalledLogo::path).collect()),
+            Err(err) => {
+                warn!("Read installed logo failed: {}", err);
+                None
+            }
+        }
+    };
+    let current_loading_icon = NativePlatform::get_loading_icon();
+    Ok(SetLogoPlan {
+        source: img_path.to_string(),
+        sha256: hex::encode(Sha256::digest(&data)),
+        destination: format!("{}/{}", LOGO_DIR, platform_info.logo_file_name(&ext)),
+        // 写入前会删除整个Logo目录
+        deleted,
+        lbldesp_old: platform_info.lbldesp_var,
+        lbldesp_new: platform_info.enabled_var(),
+        lbldvc_old: platform_info.lbldvc_var,
+        lbldvc_new,
+        loading_icon: (NativePlatform::loading_icon_supported()
+            && current_loading_icon != show_loading_icon)
+            .then_some((current_loading_icon, show_loading_icon)),
+        ext,
+        data,
+    })
+}
+
+/// 读取图片，重新编码为保守的形式（清洗模式下完全重新生成）并应用机型修正，
+/// 返回小写扩展名和数据，即设置Logo时实际写入ESP的内容
+pub fn prepare_image(img_path: &str, sanitize: bool) -> Result<(String, Vec<u8>), String> {
+    prepare_image_for(img_path, sanitize, profiles::current())
+}
+
+/// 按指定机型的修正准备图片，未知机型为None
+fn prepare_image_for(
+    img_path: &str,
+    sanitize: bool,
+    profile: Option<&DeviceProfile>,
+) -> Result<(String, Vec<u8>), String> {
+    let path = std::path::Path::new(img_path);
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or("image has no file extension")?
+        .to_ascii_lowercase();
+    let data = std::fs::read(path).map_err(|err| format!("read {}: {}", img_path, err))?;
+    if ext == "gif"
+        && let Ok(frames) = imaging::gif_frame_count(&data)
+        && frames > 1
+    {
+        warn!(
+            "{} is an animated gif with {} frames, the firmware shows at most one; \
+             flatten it to a single frame first",
+            img_path, frames
+        );
+    }
+    let data = if sanitize {
+        imaging::sanitize(data, &ext)?
+    } else {
+        imaging::normalize(data, &ext)?
+    };
+    let (ext, data) = match profile {
+        Some(profile) => profile.apply_quirks(data, &ext)?,
+        None => (ext, data),
+    };
+    if ext == "bmp" {
+        for violation in edk2_bmp::check(&data) {
+            warn!("EDK2-based firmware may reject this bmp: {}", violation);
+        }
+    }
+    Ok((ext, data))
+}
+
+/// 设置Logo：先设置Windows加载图标，再写入图片和变量，最后重新读取平台信息
+///
+/// # 参数
+/// * `img_path` - 图片路径
+/// * `sanitize` - 是否完全重新生成图片（清洗模式），见 [`imaging::sanitize`]
+/// * `show_loading_icon` - 是否显示Windows加载图标
+/// * `platform_info` - 读取到的平台信息，写入时沿用其中的变量内容和属性
+/// * `use_helper` - 是否通过pkexec启动的特权辅助进程执行
+pub fn set_logo(
+    img_path: &str,
+    sanitize: bool,
+    show_loading_icon: bool,
+    platform_info: PlatformInfo,
+    use_helper: bool,
+) -> OperationResult {
+    set_logo_image(
+        prepare_image(img_path, sanitize),
+        show_loading_icon,
+        platform_info,
+        use_helper,
+    )
+}
+
+/// 与 [`set_logo`] 相同，但写入已经读取的图片数据（如确认对话框中显示的数据）
+///
+/// # 参数
+/// * `data` - 图片数据
+/// * `ext` - 图片扩展名
+/// * `show_loading_icon` - 是否显示Windows加载图标
+/// * `platform_info` - 读取到的平台信息，写入时沿用其中的变量内容和属性
+/// * `use_helper` - 是否通过pkexec启动的特权辅助进程执行
+pub fn set_logo_data(
+    data: Vec<u8>,
+    ext: &str,
+    show_loading_icon: bool,
+    platform_info: PlatformInfo,
+    use_helper: bool,
+) -> OperationResult {
+    set_logo_image(
+        Ok((ext.to_string(), data)),
+        show_loading_icon,
+        platform_info,
+        use_helper,
+    )
+}
+
+fn set_logo_image(
+    image: Result<(String, Vec<u8>), String>,
+    show_loading_icon: bool,
+    mut platform_info: PlatformInfo,
+    use_helper: bool,
+) -> OperationResult {
+    if use_helper {
+        return perform_helper_operation(|client| {
+            let (ext, data) = image?;
+            client.set_logo(&ext, &data)?;
+            let step = progress::begin(Step::Verify);
+            let status = client.verify();
+            step.finish(matches!(status, Ok(VerifyStatus::Ok { .. })));
+            match status? {
+                VerifyStatus::Ok { file } => {
+                    info!("Change logo success, verified {}", file);
+                    Ok(())
+                }
+                status => Err(format!("verification failed: {}", status)),
+            }
+        });
+    }
+
+    // 先设置加载图标
+    let loading_icon_result = NativePlatform::set_loading_icon(show_loading_icon);
+    let new_loading_icon_state = NativePlatform::get_loading_icon();
+
+    if loading_icon_result {
+        info!("Loading icon change success");
+    } else {
+        error!("Loading icon change failed");
+    }
+
+    // 执行设置Logo操作（沿用读取时的变量内容和属性）
+    let mut success = match image {
+        Ok((ext, data)) => {
+            let success = platform_info.set_logo_bytes(&data, &ext);
+            // 记录写入的内容，供开机检查在BIOS重置后重新设置
+            if success && let Err(err) = state::record(&data, &ext, &platform_info) {
+                warn!("record applied state failed: {}", err);
+            }
+            success
+        }
+        Err(err) => {
+            error!("{}", err);
+            false
+        }
+    };
+
+    // 重新获取平台信息，界面在后台线程调用，避免在UI线程中读取
+    let mut updated_info = PlatformInfo::default();
+    let support = updated_info.get_info();
+
+    if success {
+        let step = progress::begin(Step::Verify);
+        match updated_info.verify_logo() {
+            VerifyStatus::Ok { file } => info!("Verified {}", file),
+            status => {
+                error!("Verification failed: {}", status);
+                success = false;
+            }
+        }
+        step.finish(success);
+    }
+
+    if success {
+        info!("Change logo success");
+    } else {
+        error!("Change logo failed");
+    }
+
+    OperationResult {
+        success,
+        new_loading_icon_state,
+        platform_info: updated_info,
+        support,
+    }
+}
+
+/// 恢复默认Logo，并重新启用Windows加载图标
+///
+/// # 参数
+/// * `platform_info` - 读取到的平台信息
+/// * `use_helper` - 是否通过pkexec启动的特权辅助进程执行
+pub fn restore_logo(mut platform_info: PlatformInfo, use_helper: bool) -> OperationResult {
+    if use_helper {
+        return perform_helper_operation(|client| client.restore());
+    }
+
+    // 设置加载图标为启用
+    let loading_icon_result = NativePlatform::set_loading_icon(true);
+    let new_loading_icon_state = NativePlatform::get_loading_icon();
+
+    if loading_icon_result {
+        info!("Restore loading icon success");
+    } else {
+        error!("Restore loading icon failed");
+    }
+
+    // 执行恢复Logo操作
+    let success = platform_info.restore_logo();
+
+    // 用户主动恢复默认Logo，开机检查不应再重新设置
+    if success && let Err(err) = state::clear() {
+        warn!("clear applied state failed: {}", err);
+    }
+
+    if success {
+        info!("Restore logo success");
+    } else {
+        error!("Restore logo failed");
+    }
+
+    // 重新获取平台信息，界面在后台线程调用，避免在UI线程中读取
+    let mut updated_info = PlatformInfo::default();
+    let support = updated_info.get_info();
+
+    OperationResult {
+        success,
+        new_loading_icon_state,
+        platform_info: updated_info,
+        support,
+    }
+}
+
+/// 只读地读取ESP中已安装的Logo文件
+///
+/// # 参数
+/// * `platform_info` - 读取到的平台信息，用于与LBLDVC比较
+/// * `use_helper` - 是否通过pkexec启动的特权辅助进程读取
+pub fn read_installed_logo(
+    platform_info: &PlatformInfo,
+    use_helper: bool,
+) -> Result<Vec<InstalledLogo>, String> {
+    if use_helper {
+        let files = HelperClient::spawn()?.read_logo_files()?;
+        return Ok(InstalledLogo::from_files(files, platform_info));
+    }
+    InstalledLogo::read_all(platform_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quirks_change_the_prepared_image() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/example_img/png1.png");
+        let (ext, plain) = prepare_image_for(path, true, None).unwrap();
+        assert_eq!(ext, "png");
+
+        let profile: DeviceProfile = toml::from_str(
+            "name = \"Test\"\n\
+             verified_formats = [\"jpg\"]\n\
+             broken_formats = [\"png\"]\n\
+             quirks = [\"baseline_jpeg\"]\n",
+        )
+        .unwrap();
+        let (ext, data) = prepare_image_for(path, true, Some(&profile)).unwrap();
+        assert_eq!(ext, "jpg");
+        assert_ne!(data, plain);
+        assert!(data.starts_with(&[0xFF, 0xD8]));
+        assert!(!imaging::is_progressive_jpeg(&data));
+    }
+}
@@ -1,18 +1,35 @@
-// Linux平台特定实现
+//! Linux平台特定实现
 
 use log::{debug, error, info, warn};
 use std::fs::File;
 use std::os::unix::io::AsRawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use super::{EspPartitionOps, PlatformOps};
+use crate::progress::{self, Step};
+
+use super::{
+    BgrtRaw, DiagnosticOps, DmiInfo, EspInfo, EspPartitionOps, PlatformOps, ServiceOps, TaskSpec,
+    TaskTrigger,
+};
 
 // Linux下处理EFI变量immutable属性所需的常量
 const FS_IOC_GETFLAGS: libc::c_ulong = 0x80086601;
 const FS_IOC_SETFLAGS: libc::c_ulong = 0x40086602;
 const FS_IMMUTABLE_FL: u32 = 0x00000010;
 
+/// 安装systemd unit的目录
+const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// 内核导出的ACPI BGRT表
+const BGRT_PATH: &str = "/sys/firmware/acpi/bgrt";
+
+/// 内核导出的DMI信息，序列号等文件只有root可读，这里也不读取
+const DMI_PATH: &str = "/sys/class/dmi/id";
+
+/// efivarfs的挂载位置
+const EFIVARS_PATH: &str = "/sys/firmware/efi/efivars";
+
 /// Linux平台实现
 pub struct LinuxPlatform;
 
@@ -22,6 +39,24 @@ impl PlatformOps for LinuxPlatform {
         unsafe { libc::geteuid() == 0 }
     }
 
+    fn is_uefi_boot() -> bool {
+        // 以UEFI模式启动时内核会创建 /sys/firmware/efi
+        Path::new("/sys/firmware/efi").exists()
+    }
+
+    fn efivars_available() -> bool {
+        // 检查 /proc/mounts 中是否挂载了efivarfs
+        match std::fs::read_to_string("/proc/mounts") {
+            Ok(mounts) => mounts
+                .lines()
+                .any(|line| line.split_whitespace().nth(2) == Some("efivarfs")),
+            Err(e) => {
+                warn!("Failed to read /proc/mounts: {}", e);
+                Path::new(EFIVARS_PATH).exists()
+            }
+        }
+    }
+
     fn find_available_drive() -> Option<char> {
         // Linux下不使用盘符，返回None
         // ESP分区通常挂载在 /boot/efi 或 /efi
@@ -37,33 +72,7 @@ impl PlatformOps for LinuxPlatform {
         // mount -t vfat /dev/sdX1 /mnt/esp
 
         // 这里提供一个基本实现，实际使用时可能需要更复杂的逻辑
-        let output = Command::new("mount")
-            .arg("-t")
-            .arg("vfat")
-            .arg("-o")
-            .arg("rw")
-            .arg(Self::find_esp_partition().unwrap_or("/dev/sda1".to_string()))
-            .arg(mount_point)
-            .output();
-
-        match output {
-            Ok(output) => {
-                if output.status.success() {
-                    info!("Mounted ESP partition at {}", mount_point);
-                    true
-                } else {
-                    error!(
-                        "Failed to mount ESP partition: {}",
-                        String::from_utf8_lossy(&output.stderr)
-                    );
-                    false
-                }
-            }
-            Err(e) => {
-                error!("Failed to execute mount command: {}", e);
-                false
-            }
-        }
+        Self::mount_device(&Self::esp_device(), mount_point, false)
     }
 
     fn unmount_esp(mount_point: &str) -> bool {
@@ -91,6 +100,10 @@ impl PlatformOps for LinuxPlatform {
         }
     }
 
+    fn loading_icon_supported() -> bool {
+        false
+    }
+
     fn get_loading_icon() -> bool {
         // Linux下不支持Windows加载图标功能
         // 返回默认值false
@@ -123,26 +136,122 @@ impl PlatformOps for LinuxPlatform {
 
         None
     }
+
+    fn state_dir() -> PathBuf {
+        PathBuf::from("/var/lib/lenovo-logo-changer")
+    }
+
+    fn read_bgrt() -> Result<BgrtRaw, String> {
+        let dir = Path::new(BGRT_PATH);
+        if !dir.exists() {
+            return Err("the firmware does not provide an ACPI BGRT table".to_string());
+        }
+        let read_number = |name: &str| -> Result<u32, String> {
+            let path = dir.join(name);
+            std::fs::read_to_string(&path)
+                .map_err(|err| format!("read {}: {}", path.display(), err))?
+                .trim()
+                .parse()
+                .map_err(|err| format!("parse {}: {}", path.display(), err))
+        };
+        let image_path = dir.join("image");
+        Ok(BgrtRaw {
+            image: std::fs::read(&image_path)
+                .map_err(|err| format!("read {}: {}", image_path.display(), err))?,
+            xoffset: read_number("xoffset")?,
+            yoffset: read_number("yoffset")?,
+            status: read_number("status")?,
+        })
+    }
+
+    fn boot_time() -> Option<u64> {
+        // /proc/stat中的btime为开机时间
+        std::fs::read_to_string("/proc/stat")
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("btime "))
+            .and_then(|btime| btime.trim().parse().ok())
+    }
+
+    fn local_date() -> (u32, u32) {
+        // SAFETY: localtime_r只写入传入的tm结构
+        unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut tm: libc::tm = std::mem::zeroed();
+            libc::localtime_r(&now, &mut tm);
+            ((tm.tm_mon + 1) as u32, tm.tm_mday as u32)
+        }
+    }
 }
 
 impl LinuxPlatform {
+    /// 要挂载的ESP分区设备，找不到时回退到 /dev/sda1
+    fn esp_device() -> String {
+        Self::find_esp_partition().unwrap_or_else(|| {
+            warn!("ESP partition not found, falling back to /dev/sda1");
+            "/dev/sda1".to_string()
+        })
+    }
+
+    /// 以vfat方式挂载设备，`read_only` 为true时只读挂载
+    fn mount_device(device: &str, mount_point: &str, read_only: bool) -> bool {
+        let output = Command::new("mount")
+            .arg("-t")
+            .arg("vfat")
+            .arg("-o")
+            .arg(if read_only { "ro" } else { "rw" })
+            .arg(device)
+            .arg(mount_point)
+            .output();
+
+        match output {
+            Ok(output) => {
+                if output.status.success() {
+                    info!("Mounted ESP partition at {}", mount_point);
+                    true
+                } else {
+                    error!(
+                        "Failed to mount ESP partition: {}",
+                        String::from_utf8_lossy(&output.stderr)
+                    );
+                    false
+                }
+            }
+            Err(e) => {
+                error!("Failed to execute mount command: {}", e);
+                false
+            }
+        }
+    }
+
+    /// 设备已经挂载时返回其挂载点
+    fn existing_mount(device: &str) -> Option<String> {
+        std::fs::read_to_string("/proc/mounts")
+            .ok()?
+            .lines()
+            .find_map(|line| {
+                let mut fields = line.split_whitespace();
+                (fields.next()? == device).then(|| fields.next().map(str::to_string))?
+            })
+    }
+
     /// 查找ESP分区设备
     fn find_esp_partition() -> Option<String> {
         // 使用 lsblk 或 blkid 查找ESP分区
         // ESP分区通常有 PARTTYPE="c12a7328-f81f-11d2-ba4b-00a0c93ec93b"
 
         let output = Command::new("lsblk")
-            .args(&["-o", "NAME,PARTTYPE,MOUNTPOINT", "-n", "-l"])
+            .args(["-o", "NAME,PARTTYPE,MOUNTPOINT", "-n", "-l"])
             .output();
 
         if let Ok(output) = output {
             let stdout = String::from_utf8_lossy(&output.stdout);
             for line in stdout.lines() {
                 // 查找ESP分区类型的GUID
-                if line.contains("c12a7328-f81f-11d2-ba4b-00a0c93ec93b") {
-                    if let Some(device) = line.split_whitespace().next() {
-                        return Some(format!("/dev/{}", device));
-                    }
+                if line.contains("c12a7328-f81f-11d2-ba4b-00a0c93ec93b")
+                    && let Some(device) = line.split_whitespace().next()
+                {
+                    return Some(format!("/dev/{}", device));
                 }
             }
         }
@@ -150,10 +259,10 @@ impl LinuxPlatform {
         // 如果找不到，尝试查找已挂载的EFI分区
         if let Ok(mounts) = std::fs::read_to_string("/proc/mounts") {
             for line in mounts.lines() {
-                if line.contains("/boot/efi") || line.contains("vfat") && line.contains("efi") {
-                    if let Some(device) = line.split_whitespace().next() {
-                        return Some(device.to_string());
-                    }
+                if (line.contains("/boot/efi") || line.contains("vfat") && line.contains("efi"))
+                    && let Some(device) = line.split_whitespace().next()
+                {
+                    return Some(device.to_string());
                 }
             }
         }
@@ -206,7 +315,7 @@ impl LinuxPlatform {
     where
         F: FnOnce() -> Result<(), String>,
     {
-        let var_path = Path::new("/sys/firmware/efi/efivars").join(var_name);
+        let var_path = Path::new(EFIVARS_PATH).join(var_name);
 
         if !var_path.exists() {
             return Err(format!("EFI variable {} not found", var_name));
@@ -234,7 +343,11 @@ struct EspMountGuard<'a> {
 
 impl<'a> EspMountGuard<'a> {
     fn new(mount_point: &'a str) -> Result<Self, ()> {
-        if LinuxPlatform::mount_esp(mount_point) {
+        Self::mount(&LinuxPlatform::esp_device(), mount_point, false)
+    }
+
+    fn mount(device: &str, mount_point: &'a str, read_only: bool) -> Result<Self, ()> {
+        if LinuxPlatform::mount_device(device, mount_point, read_only) {
             Ok(Self {
                 mount_point,
                 mounted: true,
@@ -243,27 +356,24 @@ impl<'a> EspMountGuard<'a> {
             Err(())
         }
     }
+
+    /// 主动卸载，返回是否成功
+    fn unmount(mut self) -> bool {
+        self.mounted = false;
+        LinuxPlatform::unmount_esp(self.mount_point)
+    }
 }
 
 impl Drop for EspMountGuard<'_> {
     fn drop(&mut self) {
-        if self.mounted {
-            if !LinuxPlatform::unmount_esp(self.mount_point) {
-                warn!("Auto-unmount ESP failed at {}", self.mount_point);
-            }
+        if self.mounted && !LinuxPlatform::unmount_esp(self.mount_point) {
+            warn!("Auto-unmount ESP failed at {}", self.mount_point);
         }
     }
 }
 
 impl EspPartitionOps for LinuxPlatform {
-    fn copy_file_to_esp(src: &str, dst: &str) -> bool {
-        // 判断src是否为文件
-        let src_path = Path::new(src);
-        if !src_path.is_file() {
-            error!("Source path is not a file");
-            return false;
-        }
-
+    fn write_file_to_esp(data: &[u8], dst: &str) -> bool {
         // Linux下通常ESP分区挂载在 /boot/efi
         // 创建临时挂载点
         let mount_point = "/tmp/lenovo_esp_mount";
@@ -274,45 +384,58 @@ impl EspPartitionOps for LinuxPlatform {
             return false;
         }
 
+        let step = progress::begin(Step::FindEsp);
+        let device = Self::esp_device();
+        step.finish(true);
+
         // 挂载ESP分区（RAII自动卸载）
-        let _guard = match EspMountGuard::new(mount_point) {
-            Ok(g) => g,
-            Err(_) => return false,
+        let step = progress::begin(Step::Mount);
+        let guard = match EspMountGuard::mount(&device, mount_point, false) {
+            Ok(g) => {
+                step.finish(true);
+                g
+            }
+            Err(_) => return step.finish(false),
         };
 
         let target_path = Path::new(mount_point).join(dst.trim_start_matches('/'));
 
         // 如果目标上级路径存在，删除目标路径
-        if let Some(parent) = target_path.parent() {
-            if parent.exists() {
-                if let Err(err) = std::fs::remove_dir_all(parent) {
-                    error!("Remove directory failed: {}", err);
-                    return false;
-                }
-            }
+        let step = progress::begin(Step::RemoveOld);
+        if let Some(parent) = target_path.parent()
+            && parent.exists()
+            && let Err(err) = std::fs::remove_dir_all(parent)
+        {
+            error!("Remove directory failed: {}", err);
+            return step.finish(false);
         }
+        step.finish(true);
 
         // 创建目标路径
-        if let Some(parent) = target_path.parent() {
-            if !parent.exists() {
-                if let Err(err) = std::fs::create_dir_all(parent) {
-                    error!("Create directory failed: {}", err);
-                    return false;
-                }
-            }
+        let step = progress::begin(Step::Copy);
+        if let Some(parent) = target_path.parent()
+            && !parent.exists()
+            && let Err(err) = std::fs::create_dir_all(parent)
+        {
+            error!("Create directory failed: {}", err);
+            return step.finish(false);
         }
 
-        // 将文件复制到目标路径
-        if let Err(err) = std::fs::copy(src, &target_path) {
+        // 将数据写入目标路径
+        if let Err(err) = std::fs::write(&target_path, data) {
             error!("Copy file failed: {}", err);
-            return false;
+            return step.finish(false);
         }
 
         info!("File copied successfully: {}", target_path.display());
 
         // 同步文件系统，确保写入
         Command::new("sync").output().ok();
+        step.finish(true);
 
+        // 文件已经写入，卸载失败不影响结果
+        let step = progress::begin(Step::Unmount);
+        step.finish(guard.unmount());
         true
     }
 
@@ -349,4 +472,320 @@ impl EspPartitionOps for LinuxPlatform {
 
         true
     }
+
+    fn read_logo_files() -> Option<Vec<(String, Vec<u8>)>> {
+        let device = Self::esp_device();
+
+        // 只读查看，已经挂载时直接读取（读写挂载的设备无法再只读挂载）
+        if let Some(existing) = Self::existing_mount(&device) {
+            debug!("ESP already mounted at {}", existing);
+            return read_dir_files(&Path::new(&existing).join("EFI/Lenovo/Logo"));
+        }
+
+        let mount_point = "/tmp/lenovo_esp_mount";
+
+        if let Err(e) = std::fs::create_dir_all(mount_point) {
+            error!("Failed to create mount point: {}", e);
+            return None;
+        }
+
+        // 只读挂载ESP分区（RAII自动卸载），查看和导出不会修改ESP
+        let _guard = EspMountGuard::mount(&device, mount_point, true).ok()?;
+
+        let logo_dir = Path::new(mount_point).join("EFI/Lenovo/Logo");
+        read_dir_files(&logo_dir)
+    }
+}
+
+/// 读取目录中的所有普通文件，目录不存在时返回空列表
+fn read_dir_files(dir: &Path) -> Option<Vec<(String, Vec<u8>)>> {
+    if !dir.exists() {
+        debug!("Logo directory does not exist");
+        return Some(Vec::new());
+    }
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("Read directory failed: {}", err);
+            return None;
+        }
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        match std::fs::read(&path) {
+            Ok(data) => files.push((entry.file_name().to_string_lossy().into_owned(), data)),
+            Err(err) => {
+                error!("Read {} failed: {}", path.display(), err);
+                return None;
+            }
+        }
+    }
+    files.sort();
+    Some(files)
+}
+
+impl DiagnosticOps for LinuxPlatform {
+    fn dmi_info() -> DmiInfo {
+        let read = |name: &str| {
+            std::fs::read_to_string(Path::new(DMI_PATH).join(name))
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        DmiInfo {
+            vendor: read("sys_vendor"),
+            product_name: read("product_name"),
+            product_version: read("product_version"),
+            bios_version: read("bios_version"),
+        }
+    }
+
+    fn efivars_mount_writable() -> Option<bool> {
+        let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+        mounts.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() >= 4 && fields[2] == "efivarfs" {
+                Some(fields[3].split(',').any(|opt| opt == "rw"))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn immutable_flag_support() -> Option<Result<(), String>> {
+        use std::io;
+
+        // 选取一个已存在的EFI变量文件，只读取flags，不做修改
+        let var_path = match std::fs::read_dir(EFIVARS_PATH) {
+            Ok(mut entries) => match entries.find_map(|e| e.ok()) {
+                Some(entry) => entry.path(),
+                None => return Some(Err("no EFI variables found".to_string())),
+            },
+            Err(e) => return Some(Err(format!("Failed to read {}: {}", EFIVARS_PATH, e))),
+        };
+
+        let file = match File::open(&var_path) {
+            Ok(file) => file,
+            Err(e) => {
+                return Some(Err(format!("Failed to open {}: {}", var_path.display(), e)));
+            }
+        };
+
+        let mut flags: u32 = 0;
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_GETFLAGS, &mut flags as *mut u32) };
+        if ret < 0 {
+            return Some(Err(format!(
+                "FS_IOC_GETFLAGS failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        debug!("{} flags: 0x{:x}", var_path.display(), flags);
+        Some(Ok(()))
+    }
+
+    fn esp_info() -> Result<EspInfo, String> {
+        let device = Self::find_esp_partition().ok_or("ESP partition not found")?;
+
+        let output = Command::new("lsblk")
+            .args(["-n", "-P", "-b", "-o", "FSTYPE,FSVER,FSAVAIL", &device])
+            .output()
+            .map_err(|e| format!("Failed to execute lsblk: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "lsblk failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        // 输出格式：FSTYPE="vfat" FSVER="FAT32" FSAVAIL="123456"
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().next().unwrap_or_default();
+        let field = |key: &str| -> String {
+            let pattern = format!("{}=\"", key);
+            line.find(&pattern)
+                .map(|start| {
+                    let rest = &line[start + pattern.len()..];
+                    rest[..rest.find('"').unwrap_or(rest.len())].to_string()
+                })
+                .unwrap_or_default()
+        };
+
+        let fs_type = format!("{} {}", field("FSTYPE"), field("FSVER"))
+            .trim()
+            .to_string();
+        let free_bytes = field("FSAVAIL").parse::<u64>().ok();
+
+        Ok(EspInfo {
+            device,
+            fs_type,
+            free_bytes,
+        })
+    }
+}
+
+impl ServiceOps for LinuxPlatform {
+    fn install_task(task: &TaskSpec) -> Result<(), String> {
+        let service_name = unit_name(task.name, "service");
+        let command = std::iter::once(task.exe.display().to_string())
+            .chain(task.args.iter().cloned())
+            .map(|arg| quote_unit_arg(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut units = Vec::new();
+        let enable = match task.trigger {
+            TaskTrigger::Boot => {
+                units.push((
+                    service_name.clone(),
+                    format!(
+                        "[Unit]\n\
+                         Description={}\n\
+                         ConditionPathExists=/sys/firmware/efi\n\
+                         After=local-fs.target\n\
+                         \n\
+                         [Service]\n\
+                         Type=oneshot\n\
+                         ExecStart={}\n\
+                         \n\
+                         [Install]\n\
+                         WantedBy=multi-user.target\n",
+                        task.description, command
+                    ),
+                ));
+                service_name
+            }
+            // 服务开机时什么都不做，关机停止服务时执行ExecStop；
+            // After=local-fs.target保证执行时文件系统仍然挂载
+            TaskTrigger::Shutdown => {
+                units.push((
+                    service_name.clone(),
+                    format!(
+                        "[Unit]\n\
+                         Description={}\n\
+                         ConditionPathExists=/sys/firmware/efi\n\
+                         After=local-fs.target\n\
+                         \n\
+                         [Service]\n\
+                         Type=oneshot\n\
+                         RemainAfterExit=yes\n\
+                         ExecStart=/bin/true\n\
+                         ExecStop={}\n\
+                         \n\
+                         [Install]\n\
+                         WantedBy=multi-user.target\n",
+                        task.description, command
+                    ),
+                ));
+                service_name
+            }
+            TaskTrigger::Daily => {
+                let timer_name = unit_name(task.name, "timer");
+                units.push((
+                    service_name,
+                    format!(
+                        "[Unit]\n\
+                         Description={}\n\
+                         ConditionPathExists=/sys/firmware/efi\n\
+                         \n\
+                         [Service]\n\
+                         Type=oneshot\n\
+                         ExecStart={}\n",
+                        task.description, command
+                    ),
+                ));
+                units.push((
+                    timer_name.clone(),
+                    format!(
+                        "[Unit]\n\
+                         Description={} (timer)\n\
+                         \n\
+                         [Timer]\n\
+                         OnCalendar=daily\n\
+                         Persistent=true\n\
+                         \n\
+                         [Install]\n\
+                         WantedBy=timers.target\n",
+                        task.description
+                    ),
+                ));
+                timer_name
+            }
+        };
+
+        for (name, contents) in &units {
+            let unit_path = Path::new(SYSTEMD_UNIT_DIR).join(name);
+            std::fs::write(&unit_path, contents)
+                .map_err(|err| format!("write {}: {}", unit_path.display(), err))?;
+            info!("Installed {}", unit_path.display());
+        }
+
+        systemctl(&["daemon-reload"])?;
+        match task.trigger {
+            TaskTrigger::Boot => systemctl(&["enable", &enable]),
+            // 需要立即启动，关机时才会有服务可停止、定时器才会开始计时
+            TaskTrigger::Shutdown | TaskTrigger::Daily => systemctl(&["enable", "--now", &enable]),
+        }
+    }
+
+    fn uninstall_task(name: &str) -> Result<(), String> {
+        let unit_paths: Vec<(String, PathBuf)> = ["timer", "service"]
+            .iter()
+            .map(|kind| unit_name(name, kind))
+            .map(|unit| {
+                let path = Path::new(SYSTEMD_UNIT_DIR).join(&unit);
+                (unit, path)
+            })
+            .filter(|(_, path)| path.exists())
+            .collect();
+        if unit_paths.is_empty() {
+            return Err(format!("{} is not installed", unit_name(name, "service")));
+        }
+
+        // 不能stop关机任务：停止服务会执行一次ExecStop，立刻轮换Logo。
+        // 只禁用并删除unit文件，daemon-reload后服务不再有ExecStop，关机时也不会执行
+        for (unit, path) in &unit_paths {
+            systemctl(&["disable", unit])?;
+            std::fs::remove_file(path)
+                .map_err(|err| format!("remove {}: {}", path.display(), err))?;
+            info!("Removed {}", path.display());
+        }
+        systemctl(&["daemon-reload"])
+    }
+}
+
+/// systemd unit文件名
+fn unit_name(task_name: &str, kind: &str) -> String {
+    format!("lenovo-logo-changer-{}.{}", task_name, kind)
+}
+
+/// ExecStart中的`%`需要转义，包含空白或引号的参数需要加引号
+fn quote_unit_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg
+    }
+}
+
+fn systemctl(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run systemctl: {}", err))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "systemctl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
 }
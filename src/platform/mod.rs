@@ -1,10 +1,12 @@
-// 平台抽象层模块
+//! 平台抽象层模块
+
+use std::path::{Path, PathBuf};
 
 // Windows平台支持（包括在Linux上交叉编译Windows目标）
-#[cfg(any(target_os = "windows", all(target_family = "windows")))]
-mod windows;
+#[cfg(any(target_os = "windows", target_family = "windows"))]
+pub mod windows;
 
-#[cfg(any(target_os = "windows", all(target_family = "windows")))]
+#[cfg(any(target_os = "windows", target_family = "windows"))]
 pub use windows::WindowsPlatform as NativePlatform;
 
 // Linux平台支持
@@ -15,11 +17,7 @@ pub mod linux;
 pub use linux::LinuxPlatform as NativePlatform;
 
 // 为未实现的平台提供编译时错误提示
-#[cfg(not(any(
-    target_os = "windows",
-    all(target_family = "windows"),
-    target_os = "linux"
-)))]
+#[cfg(not(any(target_os = "windows", target_family = "windows", target_os = "linux")))]
 compile_error!(
     "This platform is not yet supported. Currently only Windows and Linux are supported. Please add support for your platform in src/platform/"
 );
@@ -29,7 +27,14 @@ pub trait PlatformOps {
     /// 检查是否具有管理员/root权限
     fn is_admin() -> bool;
 
+    /// 检查系统是否以UEFI模式启动
+    fn is_uefi_boot() -> bool;
+
+    /// 检查EFI变量接口是否可用（Linux下即efivarfs是否已挂载）
+    fn efivars_available() -> bool;
+
     /// 查找可用的驱动器盘符（Windows特有，其他平台可能不需要）
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
     fn find_available_drive() -> Option<char>;
 
     /// 挂载ESP分区到指定路径
@@ -47,6 +52,9 @@ pub trait PlatformOps {
     /// * `mount_point` - 挂载点
     fn unmount_esp(mount_point: &str) -> bool;
 
+    /// 当前平台是否支持设置Boot加载图标（Windows的bootuxdisabled）
+    fn loading_icon_supported() -> bool;
+
     /// 获取Boot加载图标状态
     ///
     /// # 返回值
@@ -64,23 +72,134 @@ pub trait PlatformOps {
 
     /// 获取系统字体路径（用于UI显示）
     fn get_system_font_path() -> Option<String>;
+
+    /// 保存已应用状态（上次写入的图片和变量）的目录
+    fn state_dir() -> PathBuf;
+
+    /// 读取ACPI BGRT表中固件上次启动时显示的Logo
+    fn read_bgrt() -> Result<BgrtRaw, String>;
+
+    /// 本次开机的时间（Unix时间戳，秒）
+    fn boot_time() -> Option<u64>;
+
+    /// 本地日期
+    ///
+    /// # 返回值
+    /// （月，日）
+    fn local_date() -> (u32, u32);
 }
 
 /// ESP分区操作trait
 pub trait EspPartitionOps {
-    /// 复制文件到ESP分区
+    /// 将数据写入ESP分区中的文件
     ///
     /// # 参数
-    /// * `src` - 源文件路径
+    /// * `data` - 文件内容
     /// * `dst` - 目标路径（相对于ESP分区根目录）
     ///
     /// # 返回值
     /// 成功返回true，失败返回false
-    fn copy_file_to_esp(src: &str, dst: &str) -> bool;
+    fn write_file_to_esp(data: &[u8], dst: &str) -> bool;
 
     /// 删除ESP分区中的Logo路径
     ///
     /// # 返回值
     /// 成功返回true，失败返回false
     fn delete_logo_path() -> bool;
+
+    /// 只读方式读取ESP分区Logo目录（/EFI/Lenovo/Logo）中的所有文件
+    ///
+    /// # 返回值
+    /// 成功返回（文件名，内容）列表，目录不存在时返回空列表，无法访问ESP时返回None
+    fn read_logo_files() -> Option<Vec<(String, Vec<u8>)>>;
+}
+
+/// ESP分区的基本信息（只读查询结果）
+pub struct EspInfo {
+    /// 设备名或描述
+    pub device: String,
+    /// 文件系统类型，如 "vfat FAT32" 或 "FAT32"
+    pub fs_type: String,
+    /// 剩余空间（字节），未挂载时可能无法获取
+    pub free_bytes: Option<u64>,
+}
+
+/// SMBIOS/DMI中的机型信息，不包含序列号等可识别个人设备的字段
+#[derive(Clone, Debug, Default)]
+pub struct DmiInfo {
+    /// 厂商，如 "LENOVO"
+    pub vendor: Option<String>,
+    /// 产品名，联想机器为机型号，如 "21JK0000CD"
+    pub product_name: Option<String>,
+    /// 产品版本，联想机器为型号名称，如 "ThinkPad E14 Gen 5"
+    pub product_version: Option<String>,
+    /// BIOS版本
+    pub bios_version: Option<String>,
+}
+
+/// ACPI BGRT（Boot Graphics Resource Table）的原始内容
+pub struct BgrtRaw {
+    /// 固件显示的图片（BMP）
+    pub image: Vec<u8>,
+    /// 图片左上角在屏幕上的横坐标
+    pub xoffset: u32,
+    /// 图片左上角在屏幕上的纵坐标
+    pub yoffset: u32,
+    /// 状态字段，bit 0表示图片是否仍显示在屏幕上，bit 1-2为旋转方向
+    pub status: u32,
+}
+
+/// 后台任务的触发时机
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskTrigger {
+    /// 每次开机
+    Boot,
+    /// 每次关机/重启前
+    Shutdown,
+    /// 每天一次
+    Daily,
+}
+
+/// 由系统在后台运行的命令行任务
+pub struct TaskSpec<'a> {
+    /// 任务名，用于生成服务/计划任务名称，如 "check"
+    pub name: &'a str,
+    /// 任务说明
+    pub description: &'a str,
+    /// 命令行工具路径
+    pub exe: &'a Path,
+    /// 命令行参数
+    pub args: &'a [String],
+    /// 触发时机
+    pub trigger: TaskTrigger,
+}
+
+/// 后台任务trait（Linux为systemd unit，Windows为计划任务）
+pub trait ServiceOps {
+    /// 安装并启用任务，已存在时覆盖
+    fn install_task(task: &TaskSpec) -> Result<(), String>;
+
+    /// 停用并删除任务
+    fn uninstall_task(name: &str) -> Result<(), String>;
+}
+
+/// 环境诊断trait，只做只读检查，供doctor使用
+pub trait DiagnosticOps {
+    /// EFI变量接口的挂载状态
+    ///
+    /// # 返回值
+    /// None表示未挂载，Some(true)表示可写，Some(false)表示只读
+    fn efivars_mount_writable() -> Option<bool>;
+
+    /// 检查是否能通过ioctl读取EFI变量文件的immutable标志
+    ///
+    /// # 返回值
+    /// None表示该平台不需要处理immutable标志
+    fn immutable_flag_support() -> Option<Result<(), String>>;
+
+    /// 查找ESP分区并读取其文件系统类型和剩余空间
+    fn esp_info() -> Result<EspInfo, String>;
+
+    /// 读取DMI机型信息（不需要管理员/root权限）
+    fn dmi_info() -> DmiInfo;
 }
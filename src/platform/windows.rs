@@ -1,12 +1,25 @@
-// Windows平台特定实现
+//! Windows平台特定实现
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+
+use log::{debug, error, info};
 use windows_sys::{
-    Win32::Security::*, Win32::Storage::FileSystem::GetLogicalDrives, Win32::System::Threading::*,
+    Win32::Foundation::SYSTEMTIME,
+    Win32::Security::*,
+    Win32::Storage::FileSystem::{GetDiskFreeSpaceExW, GetLogicalDrives, GetVolumeInformationW},
+    Win32::System::SystemInformation::{
+        FIRMWARE_TYPE, FirmwareTypeUefi, GetFirmwareType, GetLocalTime, GetTickCount64,
+    },
+    Win32::System::Threading::*,
 };
 
-use super::{EspPartitionOps, PlatformOps};
+use crate::progress::{self, Step};
+
+use super::{
+    BgrtRaw, DiagnosticOps, DmiInfo, EspInfo, EspPartitionOps, PlatformOps, ServiceOps, TaskSpec,
+    TaskTrigger,
+};
 
 /// Windows平台实现
 pub struct WindowsPlatform;
@@ -25,11 +38,22 @@ impl PlatformOps for WindowsPlatform {
                 size,
                 &mut size,
             );
-            println!("is_admin: {}", is_admin);
+            debug!("is_admin: {}", is_admin);
             is_admin != 0
         }
     }
 
+    fn is_uefi_boot() -> bool {
+        let mut firmware_type: FIRMWARE_TYPE = 0;
+        let ok = unsafe { GetFirmwareType(&mut firmware_type) };
+        ok != 0 && firmware_type == FirmwareTypeUefi
+    }
+
+    fn efivars_available() -> bool {
+        // Windows下通过固件环境变量API访问，UEFI启动时总是可用
+        Self::is_uefi_boot()
+    }
+
     fn find_available_drive() -> Option<char> {
         // 获取逻辑驱动器的位掩码
         let drive_mask = unsafe { GetLogicalDrives() };
@@ -67,12 +91,12 @@ impl PlatformOps for WindowsPlatform {
                 if status.success() {
                     true
                 } else {
-                    eprintln!("[!] Mountvol failed / 挂载失败");
+                    error!("[!] Mountvol failed / 挂载失败");
                     false
                 }
             }
             Err(e) => {
-                eprintln!(
+                error!(
                     "[!] Mountvol command execution failed / 挂载命令执行失败: {}",
                     e
                 );
@@ -100,12 +124,12 @@ impl PlatformOps for WindowsPlatform {
                 if status.success() {
                     true
                 } else {
-                    eprintln!("[!] Unmountvol failed / 卸载失败");
+                    error!("[!] Unmountvol failed / 卸载失败");
                     false
                 }
             }
             Err(e) => {
-                eprintln!(
+                error!(
                     "[!] Unmountvol command execution failed / 卸载命令执行失败: {}",
                     e
                 );
@@ -114,6 +138,10 @@ impl PlatformOps for WindowsPlatform {
         }
     }
 
+    fn loading_icon_supported() -> bool {
+        true
+    }
+
     fn get_loading_icon() -> bool {
         // 执行 bcdedit /enum all 命令
         use std::os::windows::process::CommandExt;
@@ -129,15 +157,15 @@ impl PlatformOps for WindowsPlatform {
                 // 检查输出中是否包含 "bootuxdisabled" 和 "Yes"
                 for line in stdout.lines() {
                     if line.contains("bootuxdisabled") && line.contains("Yes") {
-                        println!("Loading icon Disabled");
+                        debug!("Loading icon Disabled");
                         return false; // 存在且包含 "Yes"
                     }
                 }
-                println!("Loading icon Enabled");
+                debug!("Loading icon Enabled");
                 true // 不存在或不包含 "Yes"
             }
             Err(e) => {
-                eprintln!("Failed to execute command: {}", e);
+                error!("Failed to execute command: {}", e);
                 true // 处理错误，返回 true
             }
         }
@@ -159,10 +187,10 @@ impl PlatformOps for WindowsPlatform {
         match cmd.output() {
             Ok(output) => {
                 if output.status.success() {
-                    println!("Command executed successfully");
+                    info!("Command executed successfully");
                     true
                 } else {
-                    eprintln!(
+                    error!(
                         "Command failed: {}",
                         String::from_utf8_lossy(&output.stderr)
                     );
@@ -170,7 +198,7 @@ impl PlatformOps for WindowsPlatform {
                 }
             }
             Err(e) => {
-                eprintln!("Failed to execute command: {}", e);
+                error!("Failed to execute command: {}", e);
                 false
             }
         }
@@ -180,69 +208,92 @@ impl PlatformOps for WindowsPlatform {
         // Windows系统字体路径
         Some("C:/Windows/Fonts/msyh.ttc".to_string())
     }
+
+    fn read_bgrt() -> Result<BgrtRaw, String> {
+        // BGRT中的图片地址是物理内存地址，用户态程序无法读取
+        Err("reading the BGRT image is only supported on Linux".to_string())
+    }
+
+    fn boot_time() -> Option<u64> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        let uptime = unsafe { GetTickCount64() } / 1000;
+        now.checked_sub(uptime)
+    }
+
+    fn local_date() -> (u32, u32) {
+        let mut time: SYSTEMTIME = unsafe { std::mem::zeroed() };
+        unsafe { GetLocalTime(&mut time) };
+        (time.wMonth as u32, time.wDay as u32)
+    }
+
+    fn state_dir() -> PathBuf {
+        let program_data =
+            std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+        PathBuf::from(program_data).join("lenovo-logo-changer")
+    }
 }
 
 impl EspPartitionOps for WindowsPlatform {
-    fn copy_file_to_esp(src: &str, dst: &str) -> bool {
-        // 判断src是否为文件
-        let src_path = Path::new(src);
-        if !src_path.is_file() {
-            eprintln!("[!] Source path is not a file / 源路径不是文件");
-            return false;
-        }
-
-        // 获取可用的盘符
+    fn write_file_to_esp(data: &[u8], dst: &str) -> bool {
+        // 获取可用的盘符，mountvol /s 由系统定位ESP分区
+        let step = progress::begin(Step::FindEsp);
         let drive_letter = match Self::find_available_drive() {
             Some(drive_letter) => drive_letter,
             None => {
-                eprintln!("[!] No available drive letter / 没有可用的盘符");
-                return false;
+                error!("[!] No available drive letter / 没有可用的盘符");
+                return step.finish(false);
             }
         };
-        println!("drive_letter: {}", drive_letter);
+        step.finish(true);
+        debug!("drive_letter: {}", drive_letter);
 
         // 挂载ESP分区
         let mount_point = drive_letter.to_string();
-        if !Self::mount_esp(&mount_point) {
+        if !progress::begin(Step::Mount).finish(Self::mount_esp(&mount_point)) {
             return false;
         }
 
         let target_path = Path::new(&format!("{}:\\", drive_letter)).join(dst);
 
         // 如果目标上级路径存在，删除目标路径
-        if let Some(parent) = target_path.parent() {
-            if parent.exists() {
-                if let Err(err) = std::fs::remove_dir_all(parent) {
-                    eprintln!("[!] Remove directory failed / 删除目录失败: {}", err);
-                    Self::unmount_esp(&mount_point);
-                    return false;
-                }
-            }
+        let step = progress::begin(Step::RemoveOld);
+        if let Some(parent) = target_path.parent()
+            && parent.exists()
+            && let Err(err) = std::fs::remove_dir_all(parent)
+        {
+            error!("[!] Remove directory failed / 删除目录失败: {}", err);
+            Self::unmount_esp(&mount_point);
+            return step.finish(false);
         }
+        step.finish(true);
 
         // 创建目标路径
-        if let Some(parent) = target_path.parent() {
-            if !parent.exists() {
-                if let Err(err) = std::fs::create_dir_all(parent) {
-                    eprintln!("[!] Create directory failed / 创建目录失败: {}", err);
-                    Self::unmount_esp(&mount_point);
-                    return false;
-                }
-            }
+        let step = progress::begin(Step::Copy);
+        if let Some(parent) = target_path.parent()
+            && !parent.exists()
+            && let Err(err) = std::fs::create_dir_all(parent)
+        {
+            error!("[!] Create directory failed / 创建目录失败: {}", err);
+            Self::unmount_esp(&mount_point);
+            return step.finish(false);
         }
 
-        // 将文件复制到目标路径
-        if let Err(err) = std::fs::copy(src, &target_path) {
-            eprintln!("[!] Copy file failed / 复制文件失败: {}", err);
+        // 将数据写入目标路径
+        if let Err(err) = std::fs::write(&target_path, data) {
+            error!("[!] Copy file failed / 复制文件失败: {}", err);
             Self::unmount_esp(&mount_point);
-            return false;
+            return step.finish(false);
         }
+        step.finish(true);
 
-        println!(
+        info!(
             "[+] File copied successfully / 文件复制成功: {}",
             target_path.display()
         );
-        Self::unmount_esp(&mount_point);
+        progress::begin(Step::Unmount).finish(Self::unmount_esp(&mount_point));
         true
     }
 
@@ -250,11 +301,11 @@ impl EspPartitionOps for WindowsPlatform {
         let drive_letter = match Self::find_available_drive() {
             Some(drive_letter) => drive_letter,
             None => {
-                eprintln!("[!] No available drive letter / 没有可用的盘符");
+                error!("[!] No available drive letter / 没有可用的盘符");
                 return false;
             }
         };
-        println!("drive_letter: {}", drive_letter);
+        debug!("drive_letter: {}", drive_letter);
 
         let mount_point = drive_letter.to_string();
         if !Self::mount_esp(&mount_point) {
@@ -266,16 +317,231 @@ impl EspPartitionOps for WindowsPlatform {
         // 如果目标路径存在，删除目标路径
         if target_path.exists() {
             if let Err(err) = std::fs::remove_dir_all(&target_path) {
-                eprintln!("[!] Remove directory failed / 删除目录失败: {}", err);
+                error!("[!] Remove directory failed / 删除目录失败: {}", err);
                 Self::unmount_esp(&mount_point);
                 return false;
             }
-            println!("[+] Logo directory deleted successfully / Logo目录删除成功");
+            info!("[+] Logo directory deleted successfully / Logo目录删除成功");
         } else {
-            println!("[*] Logo directory does not exist / Logo目录不存在");
+            info!("[*] Logo directory does not exist / Logo目录不存在");
         }
 
         Self::unmount_esp(&mount_point);
         true
     }
+
+    fn read_logo_files() -> Option<Vec<(String, Vec<u8>)>> {
+        let drive_letter = match Self::find_available_drive() {
+            Some(drive_letter) => drive_letter,
+            None => {
+                error!("[!] No available drive letter / 没有可用的盘符");
+                return None;
+            }
+        };
+
+        let mount_point = drive_letter.to_string();
+        if !Self::mount_esp(&mount_point) {
+            return None;
+        }
+
+        let logo_dir = Path::new(&format!("{}:\\", drive_letter)).join(r"EFI/Lenovo/Logo");
+        let files = read_dir_files(&logo_dir);
+
+        Self::unmount_esp(&mount_point);
+        files
+    }
+}
+
+/// 读取目录中的所有普通文件，目录不存在时返回空列表
+fn read_dir_files(dir: &Path) -> Option<Vec<(String, Vec<u8>)>> {
+    if !dir.exists() {
+        info!("[*] Logo directory does not exist / Logo目录不存在");
+        return Some(Vec::new());
+    }
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("[!] Read directory failed / 读取目录失败: {}", err);
+            return None;
+        }
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        match std::fs::read(&path) {
+            Ok(data) => files.push((entry.file_name().to_string_lossy().into_owned(), data)),
+            Err(err) => {
+                error!("[!] Read file failed / 读取文件失败: {}", err);
+                return None;
+            }
+        }
+    }
+    files.sort();
+    Some(files)
+}
+
+impl DiagnosticOps for WindowsPlatform {
+    fn dmi_info() -> DmiInfo {
+        // 系统启动时把SMBIOS中的机型信息复制到该注册表项
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+        let output = Command::new("reg")
+            .args(["query", r"HKLM\HARDWARE\DESCRIPTION\System\BIOS"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output();
+        let stdout = match output {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+            _ => return DmiInfo::default(),
+        };
+
+        // 每行格式：    SystemProductName    REG_SZ    21JK0000CD
+        let read = |name: &str| {
+            stdout.lines().find_map(|line| {
+                let mut fields = line.trim().splitn(3, "    ");
+                if fields.next()? != name || fields.next()? != "REG_SZ" {
+                    return None;
+                }
+                Some(fields.next()?.trim().to_string()).filter(|value| !value.is_empty())
+            })
+        };
+        DmiInfo {
+            vendor: read("SystemManufacturer"),
+            product_name: read("SystemProductName"),
+            product_version: read("SystemVersion"),
+            bios_version: read("BIOSVersion"),
+        }
+    }
+
+    fn efivars_mount_writable() -> Option<bool> {
+        // Windows下通过固件环境变量API访问，不存在挂载的概念
+        if Self::is_uefi_boot() {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    fn immutable_flag_support() -> Option<Result<(), String>> {
+        // Windows下EFI变量没有immutable标志
+        None
+    }
+
+    fn esp_info() -> Result<EspInfo, String> {
+        let drive_letter = Self::find_available_drive().ok_or("No available drive letter")?;
+        let mount_point = drive_letter.to_string();
+        if !Self::mount_esp(&mount_point) {
+            return Err("mountvol /s failed".to_string());
+        }
+
+        let root: Vec<u16> = format!("{}:\\", drive_letter)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let mut fs_name = [0u16; 32];
+        let ok = unsafe {
+            GetVolumeInformationW(
+                root.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                fs_name.as_mut_ptr(),
+                fs_name.len() as u32,
+            )
+        };
+        let fs_type = if ok != 0 {
+            let len = fs_name
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(fs_name.len());
+            String::from_utf16_lossy(&fs_name[..len])
+        } else {
+            String::new()
+        };
+
+        let mut free_bytes = 0u64;
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                root.as_ptr(),
+                &mut free_bytes,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+
+        Self::unmount_esp(&mount_point);
+
+        Ok(EspInfo {
+            device: "EFI System Partition (mountvol /s)".to_string(),
+            fs_type,
+            free_bytes: if ok != 0 { Some(free_bytes) } else { None },
+        })
+    }
+}
+
+impl ServiceOps for WindowsPlatform {
+    fn install_task(task: &TaskSpec) -> Result<(), String> {
+        let command = std::iter::once(task.exe.display().to_string())
+            .chain(task.args.iter().cloned())
+            .map(|arg| format!("\"{}\"", arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        // 关机没有直接的触发器，使用关机/重启时记录的User32 1074事件
+        let schedule: &[&str] = match task.trigger {
+            TaskTrigger::Boot => &["/SC", "ONSTART"],
+            TaskTrigger::Shutdown => &[
+                "/SC",
+                "ONEVENT",
+                "/EC",
+                "System",
+                "/MO",
+                "*[System[Provider[@Name='User32'] and EventID=1074]]",
+            ],
+            TaskTrigger::Daily => &["/SC", "DAILY", "/ST", "03:00"],
+        };
+        let name = task_name(task.name);
+        info!("Install scheduled task: {} ({})", name, task.description);
+        let mut args = vec!["/Create", "/F", "/TN", name.as_str()];
+        args.extend_from_slice(schedule);
+        args.extend_from_slice(&["/RU", "SYSTEM", "/RL", "HIGHEST", "/TR", command.as_str()]);
+        schtasks(&args)
+    }
+
+    fn uninstall_task(name: &str) -> Result<(), String> {
+        schtasks(&["/Delete", "/F", "/TN", &task_name(name)])
+    }
+}
+
+/// 计划任务名称
+fn task_name(name: &str) -> String {
+    format!("LenovoLogoChanger\\{}", name)
+}
+
+fn schtasks(args: &[&str]) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("schtasks")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|err| format!("failed to run schtasks: {}", err))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "schtasks failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
 }
@@ -0,0 +1,253 @@
+//! 机型数据库模块
+//!
+//! 用户在issue #34报告过的机型内置在 `assets/device_profiles.toml` 中，
+//! 按DMI中的机型号（product_name）和型号名称（product_version）匹配。
+//! 启动时据此显示"已验证机型"或"未测试机型"，写入前自动应用该机型需要的图片修正（quirks）。
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use log::{info, warn};
+use serde::Deserialize;
+
+use crate::imaging;
+use crate::lenlogo::PlatformInfo;
+use crate::platform::{DiagnosticOps, DmiInfo, NativePlatform};
+
+/// 内置的机型数据库
+const PROFILES_TOML: &str = include_str!("../assets/device_profiles.toml");
+
+/// 机型需要的图片修正
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quirk {
+    /// 固件无法解码渐进式JPEG，写入前重新编码为基线JPEG
+    BaselineJpeg,
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quirk::BaselineJpeg => write!(f, "baseline_jpeg"),
+        }
+    }
+}
+
+/// 一个已报告的机型
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceProfile {
+    /// 显示名称
+    pub name: String,
+    /// 机型号前缀，与DMI product_name比较
+    #[serde(default)]
+    pub machine_types: Vec<String>,
+    /// 型号名称，DMI product_version包含其中之一即匹配
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 已知的Logo最大宽度
+    pub width: Option<u32>,
+    /// 已知的Logo最大高度
+    pub height: Option<u32>,
+    /// 已知的校验方式版本
+    pub version: Option<u32>,
+    /// 重启后确认能显示的格式
+    #[serde(default)]
+    pub verified_formats: Vec<String>,
+    /// 固件接受但不显示的格式
+    #[serde(default)]
+    pub broken_formats: Vec<String>,
+    /// 写入前自动应用的图片修正
+    #[serde(default)]
+    pub quirks: Vec<Quirk>,
+}
+
+#[derive(Deserialize)]
+struct ProfileDatabase {
+    device: Vec<DeviceProfile>,
+}
+
+impl DeviceProfile {
+    /// 是否与DMI信息匹配，忽略大小写
+    pub fn matches(&self, dmi: &DmiInfo) -> bool {
+        let product_name = dmi.product_name.as_deref().unwrap_or_default();
+        let product_version = dmi.product_version.as_deref().unwrap_or_default();
+        self.machine_types.iter().any(|machine_type| {
+            !product_name.is_empty()
+                && product_name
+                    .to_ascii_lowercase()
+                    .starts_with(&machine_type.to_ascii_lowercase())
+        }) || self.models.iter().any(|model| {
+            !product_version.is_empty()
+                && product_version
+                    .to_ascii_lowercase()
+                    .contains(&model.to_ascii_lowercase())
+        })
+    }
+
+    /// 格式是否已知无法显示
+    pub fn is_broken_format(&self, ext: &str) -> bool {
+        let ext = normalize_ext(ext);
+        self.broken_formats
+            .iter()
+            .any(|format| normalize_ext(format) == ext)
+    }
+
+    /// 与固件中读取的设置比较，不一致时返回说明（可能是新的BIOS版本）
+    pub fn differences(&self, platform_info: &PlatformInfo) -> Vec<String> {
+        let mut differences = Vec::new();
+        if let (Some(width), Some(height)) = (self.width, self.height)
+            && (width, height) != (platform_info.width, platform_info.height)
+        {
+            differences.push(format!(
+                "maximum size is {}x{}, known {}x{}",
+                platform_info.width, platform_info.height, width, height
+            ));
+        }
+        if let Some(version) = self.version
+            && version != platform_info.version
+        {
+            differences.push(format!(
+                "version is 0x{:x}, known 0x{:x}",
+                platform_info.version, version
+            ));
+        }
+        differences
+    }
+
+    /// 按该机型的修正处理要写入的图片，返回扩展名和数据，不需要修正时原样返回
+    ///
+    /// 已知无法显示的格式转换为该机型验证过的格式，没有验证过的格式时拒绝写入
+    pub fn apply_quirks(&self, data: Vec<u8>, ext: &str) -> Result<(String, Vec<u8>), String> {
+        let mut ext = normalize_ext(ext);
+        let mut data = data;
+        if self.is_broken_format(&ext) {
+            let Some(target) = self
+                .verified_formats
+                .iter()
+                .find(|format| !self.is_broken_format(format))
+            else {
+                return Err(format!(
+                    "format {} is known not to display on {}, choose another format",
+                    ext, self.name
+                ));
+            };
+            let target = normalize_ext(target);
+            info!(
+                "Converting {} to {}, {} does not display {}",
+                ext, target, self.name, ext
+            );
+            data = imaging::convert(&data, &ext, &target)?;
+            ext = target;
+        }
+        for quirk in &self.quirks {
+            match quirk {
+                Quirk::BaselineJpeg => {
+                    if ext == "jpg" && imaging::is_progressive_jpeg(&data) {
+                        info!("Re-encoding progressive JPEG as baseline for {}", self.name);
+                        data = imaging::to_baseline_jpeg(&data)?;
+                    }
+                }
+            }
+        }
+        Ok((ext, data))
+    }
+}
+
+fn normalize_ext(ext: &str) -> String {
+    match ext.to_ascii_lowercase().as_str() {
+        "jpeg" => "jpg".to_string(),
+        ext => ext.to_string(),
+    }
+}
+
+/// 内置的所有机型
+pub fn profiles() -> &'static [DeviceProfile] {
+    static PROFILES: OnceLock<Vec<DeviceProfile>> = OnceLock::new();
+    PROFILES.get_or_init(|| match toml::from_str::<ProfileDatabase>(PROFILES_TOML) {
+        Ok(database) => database.device,
+        Err(err) => {
+            warn!("Invalid built-in device profiles: {}", err);
+            Vec::new()
+        }
+    })
+}
+
+/// 查找与DMI信息匹配的机型
+pub fn lookup(dmi: &DmiInfo) -> Option<&'static DeviceProfile> {
+    profiles().iter().find(|profile| profile.matches(dmi))
+}
+
+/// 当前设备的机型，只读取一次DMI
+pub fn current() -> Option<&'static DeviceProfile> {
+    static CURRENT: OnceLock<Option<&'static DeviceProfile>> = OnceLock::new();
+    *CURRENT.get_or_init(|| lookup(&NativePlatform::dmi_info()))
+}
+
+/// 按当前设备的修正处理要写入的图片，未知机型原样返回
+pub fn apply_quirks(data: Vec<u8>, ext: &str) -> Result<(String, Vec<u8>), String> {
+    match current() {
+        Some(profile) => profile.apply_quirks(data, ext),
+        None => Ok((ext.to_string(), data)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(toml: &str) -> DeviceProfile {
+        toml::from_str(toml).expect("test profile parses")
+    }
+
+    #[test]
+    fn builtin_database_parses() {
+        let database: ProfileDatabase =
+            toml::from_str(PROFILES_TOML).expect("assets/device_profiles.toml parses");
+        assert!(!database.device.is_empty());
+        for profile in &database.device {
+            assert!(
+                !profile.machine_types.is_empty() || !profile.models.is_empty(),
+                "{} has nothing to match on",
+                profile.name
+            );
+            assert!(
+                profile.quirks.contains(&Quirk::BaselineJpeg),
+                "{} has no baseline_jpeg quirk",
+                profile.name
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_matches_model_name() {
+        let dmi = DmiInfo {
+            product_version: Some("thinkpad x13 gen 4".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            lookup(&dmi).map(|p| p.name.as_str()),
+            Some("ThinkPad X13 Gen 4")
+        );
+        assert!(lookup(&DmiInfo::default()).is_none());
+    }
+
+    #[test]
+    fn progressive_jpeg_detected_from_frame_header() {
+        // SOI、APP0（长度2）、SOF2
+        let progressive = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x02, 0xFF, 0xC2, 0x00, 0x02];
+        let baseline = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x02, 0xFF, 0xC0, 0x00, 0x02];
+        assert!(imaging::is_progressive_jpeg(&progressive));
+        assert!(!imaging::is_progressive_jpeg(&baseline));
+    }
+
+    #[test]
+    fn broken_format_without_verified_format_is_refused() {
+        let profile = profile("name = \"Test\"\nbroken_formats = [\"png\"]\n");
+        let err = profile.apply_quirks(Vec::new(), "PNG").unwrap_err();
+        assert!(err.contains("png"), "{}", err);
+        // 其他格式不受影响
+        let (ext, data) = profile.apply_quirks(vec![1, 2, 3], "jpeg").unwrap();
+        assert_eq!((ext.as_str(), data), ("jpg", vec![1, 2, 3]));
+    }
+}
@@ -0,0 +1,209 @@
+//! 进度事件模块
+//!
+//! 设置Logo时每个步骤开始和结束都会发出进度事件，界面和命令行据此显示步骤清单。
+//! 挂载、复制等步骤位于平台实现深处，逐层传递参数会改动所有接口，
+//! 因此事件发送到当前线程登记的处理函数：调用方用 [`with_sender`] 或 [`with_handler`]
+//! 包住操作，操作中通过 [`begin`] 报告步骤。没有登记处理函数时事件被忽略。
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+/// 设置Logo的步骤，按执行顺序排列
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// 计算图片的校验值（写入前计算，版本不支持时不会写入任何内容）
+    Hash,
+    /// 查找ESP分区
+    FindEsp,
+    /// 挂载ESP分区
+    Mount,
+    /// 删除旧的Logo目录
+    RemoveOld,
+    /// 复制图片到ESP
+    Copy,
+    /// 卸载ESP分区
+    Unmount,
+    /// 写入LBLDESP
+    WriteLbldesp,
+    /// 写入LBLDVC
+    WriteLbldvc,
+    /// 校验已安装的Logo
+    Verify,
+}
+
+impl Step {
+    /// 所有步骤，按执行顺序
+    pub const ALL: [Step; 9] = [
+        Step::Hash,
+        Step::FindEsp,
+        Step::Mount,
+        Step::RemoveOld,
+        Step::Copy,
+        Step::Unmount,
+        Step::WriteLbldesp,
+        Step::WriteLbldvc,
+        Step::Verify,
+    ];
+
+    /// 稳定的步骤代码，用于辅助进程协议和i18n键
+    pub fn code(&self) -> &'static str {
+        match self {
+            Step::Hash => "hash",
+            Step::FindEsp => "find_esp",
+            Step::Mount => "mount",
+            Step::RemoveOld => "remove_old",
+            Step::Copy => "copy",
+            Step::Unmount => "unmount",
+            Step::WriteLbldesp => "write_lbldesp",
+            Step::WriteLbldvc => "write_lbldvc",
+            Step::Verify => "verify",
+        }
+    }
+
+    /// 由步骤代码得到步骤
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.code() == code)
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Step::Hash => "Hash image",
+            Step::FindEsp => "Find ESP",
+            Step::Mount => "Mount ESP",
+            Step::RemoveOld => "Remove old logo",
+            Step::Copy => "Copy image",
+            Step::Unmount => "Unmount ESP",
+            Step::WriteLbldesp => "Write LBLDESP",
+            Step::WriteLbldvc => "Write LBLDVC",
+            Step::Verify => "Verify",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// 步骤状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepStatus {
+    /// 正在执行
+    Running,
+    /// 已完成，附带耗时
+    Done(Duration),
+    /// 失败，附带耗时，原因见日志
+    Failed(Duration),
+}
+
+/// 进度事件
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgressEvent {
+    /// 步骤
+    pub step: Step,
+    /// 状态
+    pub status: StepStatus,
+}
+
+impl ProgressEvent {
+    /// 编码为一行文本，用于辅助进程协议：`<步骤> running`、`<步骤> done <毫秒>`、`<步骤> failed <毫秒>`
+    pub fn encode(&self) -> String {
+        match self.status {
+            StepStatus::Running => format!("{} running", self.step.code()),
+            StepStatus::Done(elapsed) => {
+                format!("{} done {}", self.step.code(), elapsed.as_millis())
+            }
+            StepStatus::Failed(elapsed) => {
+                format!("{} failed {}", self.step.code(), elapsed.as_millis())
+            }
+        }
+    }
+
+    /// 解析 [`encode`](Self::encode) 的结果
+    pub fn decode(line: &str) -> Option<Self> {
+        let mut parts = line.split(' ');
+        let step = Step::from_code(parts.next()?)?;
+        let state = parts.next()?;
+        let mut elapsed = || {
+            parts
+                .next()
+                .and_then(|ms| ms.parse().ok())
+                .map(Duration::from_millis)
+        };
+        let status = match state {
+            "running" => StepStatus::Running,
+            "done" => StepStatus::Done(elapsed()?),
+            "failed" => StepStatus::Failed(elapsed()?),
+            _ => return None,
+        };
+        Some(Self { step, status })
+    }
+}
+
+type Handler = Box<dyn FnMut(&ProgressEvent)>;
+
+thread_local! {
+    static HANDLER: RefCell<Option<Handler>> = const { RefCell::new(None) };
+}
+
+/// 在当前线程执行 `f`，期间的进度事件交给 `handler` 处理
+pub fn with_handler<T>(handler: impl FnMut(&ProgressEvent) + 'static, f: impl FnOnce() -> T) -> T {
+    let previous = HANDLER.with(|cell| cell.replace(Some(Box::new(handler))));
+    let result = f();
+    HANDLER.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+/// 在当前线程执行 `f`，期间的进度事件发送到channel
+pub fn with_sender<T>(sender: Sender<ProgressEvent>, f: impl FnOnce() -> T) -> T {
+    with_handler(
+        move |event| {
+            // 接收端已关闭（界面已不关心进度）时忽略
+            let _ = sender.send(*event);
+        },
+        f,
+    )
+}
+
+/// 把事件交给当前线程的处理函数，辅助进程客户端用来转发辅助进程报告的事件
+pub fn emit(event: ProgressEvent) {
+    HANDLER.with(|cell| {
+        if let Some(handler) = cell.borrow_mut().as_mut() {
+            handler(&event);
+        }
+    });
+}
+
+/// 开始一个步骤，发出 `Running` 事件
+pub fn begin(step: Step) -> StepTimer {
+    emit(ProgressEvent {
+        step,
+        status: StepStatus::Running,
+    });
+    StepTimer {
+        step,
+        started: Instant::now(),
+    }
+}
+
+/// 正在执行的步骤，结束时发出带耗时的事件
+pub struct StepTimer {
+    step: Step,
+    started: Instant,
+}
+
+impl StepTimer {
+    /// 结束步骤，`ok` 为false时报告失败；返回 `ok` 便于直接用于判断
+    pub fn finish(self, ok: bool) -> bool {
+        let elapsed = self.started.elapsed();
+        emit(ProgressEvent {
+            step: self.step,
+            status: if ok {
+                StepStatus::Done(elapsed)
+            } else {
+                StepStatus::Failed(elapsed)
+            },
+        });
+        ok
+    }
+}
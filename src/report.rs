@@ -0,0 +1,348 @@
+//! 兼容性报告模块
+//!
+//! README请用户在issue #34报告可用的机型，但报告内容参差不齐。
+//! 这里收集机型、固件变量、上次操作结果和BGRT状态，生成可以直接粘贴的Markdown或JSON。
+//! 只读取DMI中的厂商、型号和BIOS版本，不包含序列号、UUID等可识别具体设备的信息。
+
+use serde::Serialize;
+
+use crate::bgrt::Bgrt;
+use crate::confirm;
+use crate::platform::{DiagnosticOps, DmiInfo, NativePlatform};
+use crate::profiles;
+use crate::state;
+use crate::status::{ConfirmationJson, DiagnosisJson, PlatformJson, SCHEMA_VERSION, StatusReport};
+
+/// 兼容性报告，对应JSON输出的顶层对象
+#[derive(Serialize)]
+pub struct CompatReport {
+    /// 格式版本，与状态报告相同
+    pub schema_version: u32,
+    /// 工具版本
+    pub tool_version: &'static str,
+    /// 操作系统，如 `linux`、`windows`
+    pub os: &'static str,
+    /// 机型信息
+    pub dmi: DmiJson,
+    /// 机型数据库中匹配的机型名称，未测试的机型为 `null`
+    pub profile: Option<String>,
+    /// 设备是否支持修改Logo
+    pub supported: bool,
+    /// 不支持的原因，支持时为 `null`
+    pub diagnosis: Option<DiagnosisJson>,
+    /// 固件中的Logo设置，变量无法读取时为 `null`
+    pub platform: Option<PlatformJson>,
+    /// 上次成功设置的Logo，没有记录时为 `null`
+    pub last_applied: Option<LastAppliedJson>,
+    /// 界面中上次操作的结果，命令行为 `null`
+    pub last_operation: Option<String>,
+    /// 上次开机是否显示了自定义Logo
+    pub boot_confirmation: ConfirmationJson,
+    /// 上次开机的BGRT
+    pub bgrt: BgrtJson,
+}
+
+/// DMI机型信息，无法读取的字段为 `null`
+#[derive(Serialize)]
+pub struct DmiJson {
+    /// 厂商
+    pub vendor: Option<String>,
+    /// 产品名（联想机器为机型号）
+    pub product_name: Option<String>,
+    /// 产品版本（联想机器为型号名称）
+    pub product_version: Option<String>,
+    /// BIOS版本
+    pub bios_version: Option<String>,
+}
+
+/// 上次成功设置的Logo
+#[derive(Serialize)]
+pub struct LastAppliedJson {
+    /// 图片格式
+    pub ext: String,
+    /// 写入时间（Unix时间戳，秒）
+    pub applied_at: u64,
+}
+
+/// BGRT状态
+#[derive(Serialize)]
+pub struct BgrtJson {
+    /// 是否能读取并解码BGRT图片
+    pub available: bool,
+    /// 图片宽度
+    pub width: Option<u32>,
+    /// 图片高度
+    pub height: Option<u32>,
+    /// 图片在屏幕上的位置
+    pub offset: Option<(u32, u32)>,
+    /// BGRT状态字段
+    pub status: Option<u32>,
+    /// 无法读取的原因
+    pub error: Option<String>,
+}
+
+impl From<DmiInfo> for DmiJson {
+    fn from(dmi: DmiInfo) -> Self {
+        Self {
+            vendor: dmi.vendor,
+            product_name: dmi.product_name,
+            product_version: dmi.product_version,
+            bios_version: dmi.bios_version,
+        }
+    }
+}
+
+impl CompatReport {
+    /// 收集报告内容（不需要管理员/root权限，不读取ESP）
+    ///
+    /// # 参数
+    /// * `last_operation` - 界面中上次操作的结果，没有时为None
+    pub fn collect(last_operation: Option<String>) -> Self {
+        let (status, _) = StatusReport::collect();
+        let confirmation = confirm::confirm();
+        let bgrt = match Bgrt::read() {
+            Ok(bgrt) => BgrtJson {
+                available: true,
+                width: Some(bgrt.dimensions().0),
+                height: Some(bgrt.dimensions().1),
+                offset: Some((bgrt.xoffset, bgrt.yoffset)),
+                status: Some(bgrt.status),
+                error: None,
+            },
+            Err(err) => BgrtJson {
+                available: false,
+                width: None,
+                height: None,
+                offset: None,
+                status: None,
+                error: Some(err),
+            },
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tool_version: status.tool_version,
+            os: std::env::consts::OS,
+            dmi: NativePlatform::dmi_info().into(),
+            profile: profiles::current().map(|profile| profile.name.clone()),
+            supported: status.supported,
+            diagnosis: status.diagnosis,
+            platform: status.platform,
+            last_applied: state::load()
+                .ok()
+                .flatten()
+                .map(|(state, _)| LastAppliedJson {
+                    ext: state.ext,
+                    applied_at: state.applied_at,
+                }),
+            last_operation,
+            boot_confirmation: ConfirmationJson {
+                result: confirmation.code(),
+                message: confirmation.to_string(),
+                similarity: confirmation.similarity(),
+            },
+            bgrt,
+        }
+    }
+
+    /// 输出格式化的JSON
+    pub fn to_json(&self) -> String {
+        // 只包含字符串、数字和数组，序列化不会失败
+        serde_json::to_string_pretty(self).expect("compatibility report serializes to JSON")
+    }
+
+    /// 输出Markdown表格，用于粘贴到issue
+    pub fn to_markdown(&self) -> String {
+        let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "unknown".to_string());
+        let mut rows = vec![
+            ("Vendor", text(&self.dmi.vendor)),
+            ("Product name", text(&self.dmi.product_name)),
+            ("Product version", text(&self.dmi.product_version)),
+            ("BIOS version", text(&self.dmi.bios_version)),
+            (
+                "Device database",
+                match &self.profile {
+                    Some(name) => format!("known good ({})", name),
+                    None => "untested".to_string(),
+                },
+            ),
+            ("OS", self.os.to_string()),
+            ("Tool version", self.tool_version.to_string()),
+            ("Supported", self.supported.to_string()),
+        ];
+        if let Some(diagnosis) = &self.diagnosis {
+            rows.push((
+                "Diagnosis",
+                format!("`{}` {}", diagnosis.code, diagnosis.message),
+            ));
+        }
+        if let Some(platform) = &self.platform {
+            rows.extend([
+                ("LBLDESP", format!("`{}`", platform.lbldesp)),
+                ("LBLDVC", format!("`{}`", platform.lbldvc)),
+                ("Enabled", platform.enable.to_string()),
+                (
+                    "Max image size",
+                    format!("{}x{}", platform.width, platform.height),
+                ),
+                (
+                    "Version",
+                    format!(
+                        "{} ({})",
+                        platform.version,
+                        platform.checksum.unwrap_or("unknown checksum")
+                    ),
+                ),
+                ("Supported formats", platform.support.join(", ")),
+            ]);
+        }
+        rows.push((
+            "Last applied",
+            match &self.last_applied {
+                Some(applied) => format!("{} at {}", applied.ext, applied.applied_at),
+                None => "none".to_string(),
+            },
+        ));
+        if let Some(operation) = &self.last_operation {
+            rows.push(("Last operation", operation.clone()));
+        }
+        rows.push((
+            "Boot confirmation",
+            format!(
+                "`{}` {}",
+                self.boot_confirmation.result, self.boot_confirmation.message
+            ),
+        ));
+        rows.push((
+            "BGRT",
+            match (&self.bgrt.error, self.bgrt.width, self.bgrt.height) {
+                (Some(err), _, _) => format!("unavailable: {}", err),
+                (None, Some(width), Some(height)) => format!(
+                    "{}x{} at {:?}, status 0x{:x}",
+                    width,
+                    height,
+                    self.bgrt.offset.unwrap_or_default(),
+                    self.bgrt.status.unwrap_or_default()
+                ),
+                _ => "unavailable".to_string(),
+            },
+        ));
+
+        let mut out = String::from("| Field | Value |\n| --- | --- |\n");
+        for (field, value) in rows {
+            // 表格单元格中的 `|` 和换行会破坏表格
+            let value = value.replace('|', "\\|").replace('\n', " ");
+            out.push_str(&format!("| {} | {} |\n", field, value));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 不应出现在报告中的DMI字段
+    const IDENTIFYING_FIELDS: [&str; 4] = [
+        "product_serial",
+        "product_uuid",
+        "board_serial",
+        "chassis_serial",
+    ];
+
+    fn assert_anonymous(report: &CompatReport) {
+        let (json, markdown) = (report.to_json(), report.to_markdown());
+        for output in [&json, &markdown] {
+            let lower = output.to_lowercase();
+            assert!(!lower.contains("serial"), "{}", output);
+            assert!(!lower.contains("uuid"), "{}", output);
+        }
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let mut keys: Vec<&str> = value["dmi"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            ["bios_version", "product_name", "product_version", "vendor"]
+        );
+
+        // 在真实机器上，序列号和UUID的值也不能出现在报告中
+        let reported = [
+            &report.dmi.vendor,
+            &report.dmi.product_name,
+            &report.dmi.product_version,
+            &report.dmi.bios_version,
+        ];
+        for field in IDENTIFYING_FIELDS {
+            let Ok(value) = std::fs::read_to_string(format!("/sys/class/dmi/id/{}", field)) else {
+                continue;
+            };
+            let value = value.trim();
+            // 占位值（如 "To be filled by O.E.M."）可能与其他字段相同
+            if value.is_empty() || reported.iter().any(|r| r.as_deref() == Some(value)) {
+                continue;
+            }
+            assert!(!json.contains(value), "{} leaked into JSON", field);
+            assert!(!markdown.contains(value), "{} leaked into Markdown", field);
+        }
+    }
+
+    #[test]
+    fn sample_report_has_no_serial_or_uuid() {
+        let report = CompatReport {
+            schema_version: SCHEMA_VERSION,
+            tool_version: "0.0.0",
+            os: "linux",
+            dmi: DmiInfo {
+                vendor: Some("LENOVO".to_string()),
+                product_name: Some("21JK0000CD".to_string()),
+                product_version: Some("ThinkPad E14 Gen 5".to_string()),
+                bios_version: Some("R2AET40W (1.15 )".to_string()),
+            }
+            .into(),
+            profile: Some("ThinkPad E14 Gen 5".to_string()),
+            supported: true,
+            diagnosis: None,
+            platform: Some(PlatformJson {
+                enable: true,
+                width: 1920,
+                height: 1080,
+                version: "0x20003".to_string(),
+                checksum: Some("crc32"),
+                support: vec!["jpg", "bmp", "png"],
+                lbldesp: "01".to_string(),
+                lbldesp_attributes: 7,
+                lbldvc: "00".to_string(),
+                lbldvc_attributes: 7,
+            }),
+            last_applied: Some(LastAppliedJson {
+                ext: "png".to_string(),
+                applied_at: 1_700_000_000,
+            }),
+            last_operation: Some("set logo succeeded".to_string()),
+            boot_confirmation: ConfirmationJson {
+                result: "confirmed",
+                message: "the firmware displayed the custom logo".to_string(),
+                similarity: Some(0.98),
+            },
+            bgrt: BgrtJson {
+                available: true,
+                width: Some(400),
+                height: Some(300),
+                offset: Some((760, 390)),
+                status: Some(1),
+                error: None,
+            },
+        };
+        assert_anonymous(&report);
+    }
+
+    #[test]
+    fn collected_report_has_no_serial_or_uuid() {
+        assert_anonymous(&CompatReport::collect(None));
+    }
+}
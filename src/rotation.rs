@@ -0,0 +1,370 @@
+//! Logo轮换模块
+//!
+//! 从图片库目录中按策略选出一张图片，通过 [`apply`] 设置为下次开机的Logo。
+//! 配合关机任务或定时器使用，每次开机显示不同的Logo。
+//!
+//! 配置文件格式（TOML）：
+//!
+//! ```toml
+//! # 图片库目录，相对路径相对于配置文件所在目录
+//! library = "logos"
+//! # "random"（随机）、"sequential"（按文件名依次）或 "date"（按日期规则）
+//! policy = "date"
+//! # 超过固件最大分辨率时的处理方式："none" 或 "fit"
+//! resize = "fit"
+//! # 是否完全重新生成图片（清洗模式），默认true
+//! sanitize = true
+//! # date策略下没有规则匹配时使用的图片，不设置时保持当前Logo
+//! default = "lenovo.png"
+//!
+//! # 日期规则，按顺序匹配，日期格式为 MM-DD，包含首尾两天，可以跨年
+//! [[rules]]
+//! from = "12-20"
+//! to = "01-02"
+//! image = "christmas.png"
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::apply::{self, ApplyConfig, ApplyReport};
+use crate::imaging::ResizePolicy;
+use crate::lenlogo::PlatformInfo;
+use crate::platform::{NativePlatform, PlatformOps};
+
+/// 轮换进度文件名（保存在状态目录）
+const ROTATION_FILE: &str = "rotation.json";
+
+/// 选图策略
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationPolicy {
+    /// 随机选择，图片多于一张时不会连续选中同一张
+    Random,
+    /// 按文件名顺序依次选择
+    Sequential,
+    /// 按日期规则选择
+    Date,
+}
+
+/// 日期规则
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DateRule {
+    /// 开始日期，MM-DD
+    pub from: String,
+    /// 结束日期（包含），MM-DD
+    pub to: String,
+    /// 图片库中的文件名
+    pub image: String,
+}
+
+/// 轮换配置
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotationConfig {
+    /// 图片库目录
+    pub library: PathBuf,
+    /// 选图策略
+    pub policy: RotationPolicy,
+    /// 超过固件最大分辨率时的处理方式
+    #[serde(default)]
+    pub resize: ResizePolicy,
+    /// 是否完全重新生成图片（清洗模式），默认开启
+    #[serde(default = "apply::default_sanitize")]
+    pub sanitize: bool,
+    /// date策略下没有规则匹配时使用的图片
+    pub default: Option<String>,
+    /// 日期规则
+    #[serde(default)]
+    pub rules: Vec<DateRule>,
+}
+
+impl RotationConfig {
+    /// 读取配置文件并检查日期规则
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("read {}: {}", path.display(), err))?;
+        let mut config: Self =
+            toml::from_str(&text).map_err(|err| format!("parse {}: {}", path.display(), err))?;
+        if config.library.is_relative()
+            && let Some(dir) = path.parent()
+        {
+            config.library = dir.join(&config.library);
+        }
+        for rule in &config.rules {
+            parse_month_day(&rule.from)?;
+            parse_month_day(&rule.to)?;
+        }
+        if config.policy == RotationPolicy::Date && config.rules.is_empty() {
+            return Err("policy \"date\" requires at least one [[rules]] entry".to_string());
+        }
+        Ok(config)
+    }
+}
+
+/// 上次选中的图片
+#[derive(Default, Serialize, Deserialize)]
+struct RotationState {
+    last: Option<String>,
+}
+
+fn state_path() -> PathBuf {
+    NativePlatform::state_dir().join(ROTATION_FILE)
+}
+
+fn load_state() -> RotationState {
+    std::fs::read_to_string(state_path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &RotationState) -> Result<(), String> {
+    let dir = NativePlatform::state_dir();
+    std::fs::create_dir_all(&dir).map_err(|err| format!("create {}: {}", dir.display(), err))?;
+    let json = serde_json::to_string_pretty(state).map_err(|err| err.to_string())?;
+    std::fs::write(state_path(), json)
+        .map_err(|err| format!("write {}: {}", state_path().display(), err))
+}
+
+/// 解析 MM-DD
+fn parse_month_day(text: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid date \"{}\", expected MM-DD", text);
+    let (month, day) = text.split_once('-').ok_or_else(invalid)?;
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    let day: u32 = day.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+    Ok((month, day))
+}
+
+/// 日期是否在规则范围内，`from` 晚于 `to` 时表示跨年
+fn rule_matches(rule: &DateRule, today: (u32, u32)) -> bool {
+    let (Ok(from), Ok(to)) = (parse_month_day(&rule.from), parse_month_day(&rule.to)) else {
+        return false;
+    };
+    if from <= to {
+        from <= today && today <= to
+    } else {
+        today >= from || today <= to
+    }
+}
+
+/// 图片库中固件支持的图片，按文件名排序
+fn list_library(library: &Path, support: &[&str]) -> Result<Vec<String>, String> {
+    let entries =
+        std::fs::read_dir(library).map_err(|err| format!("read {}: {}", library.display(), err))?;
+    let mut images: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| {
+            let ext = Path::new(name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(str::to_ascii_lowercase);
+            match ext.as_deref() {
+                Some("jpeg") => support.contains(&"jpg"),
+                Some(ext) => support.contains(&ext),
+                None => false,
+            }
+        })
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// 不需要加密强度，用时间和进程号生成随机数即可
+fn random_index(len: usize) -> usize {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut x = (nanos as u64) ^ ((std::process::id() as u64) << 32);
+    // xorshift64
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x % len as u64) as usize
+}
+
+/// 按策略选出下一张图片，返回图片库中的文件名；date策略没有规则匹配且没有默认图片时返回None
+fn pick(
+    config: &RotationConfig,
+    images: &[String],
+    last: Option<&str>,
+    today: (u32, u32),
+) -> Result<Option<String>, String> {
+    match config.policy {
+        RotationPolicy::Date => Ok(config
+            .rules
+            .iter()
+            .find(|rule| rule_matches(rule, today))
+            .map(|rule| rule.image.clone())
+            .or_else(|| config.default.clone())),
+        _ if images.is_empty() => Err(format!(
+            "no supported images in {}",
+            config.library.display()
+        )),
+        RotationPolicy::Sequential => {
+            let next = last
+                .and_then(|last| images.iter().position(|name| name == last))
+                .map_or(0, |pos| (pos + 1) % images.len());
+            Ok(Some(images[next].clone()))
+        }
+        RotationPolicy::Random => {
+            let candidates: Vec<&String> = images
+                .iter()
+                .filter(|name| images.len() == 1 || Some(name.as_str()) != last)
+                .collect();
+            Ok(Some(candidates[random_index(candidates.len())].clone()))
+        }
+    }
+}
+
+/// 轮换结果
+pub struct RotationResult {
+    /// 选中的图片，没有选中时为None
+    pub image: Option<String>,
+    /// 设置Logo的结果，没有选中图片时为None
+    pub report: Option<ApplyReport>,
+}
+
+/// 选出下一张图片并设置为Logo（需要管理员/root权限）
+///
+/// # 参数
+/// * `config` - 轮换配置
+/// * `check` - 只选图和比较，不写入，也不推进轮换进度
+pub fn rotate(config: &RotationConfig, check: bool) -> Result<RotationResult, String> {
+    let mut platform_info = PlatformInfo::default();
+    platform_info
+        .get_info()
+        .map_err(|diag| format!("device not supported: {}", diag))?;
+
+    let images = list_library(&config.library, &platform_info.support)?;
+    let mut state = load_state();
+    let Some(image) = pick(
+        config,
+        &images,
+        state.last.as_deref(),
+        NativePlatform::local_date(),
+    )?
+    else {
+        info!("No date rule matches today, keeping the current logo");
+        return Ok(RotationResult {
+            image: None,
+            report: None,
+        });
+    };
+    info!("Rotation picked {}", image);
+
+    let apply_config = ApplyConfig {
+        image: config.library.join(&image),
+        resize: config.resize,
+        loading_icon: None,
+        sanitize: config.sanitize,
+    };
+    let report = apply::apply(&apply_config, check);
+
+    if !check && report.error.is_none() {
+        state.last = Some(image.clone());
+        save_state(&state)?;
+    }
+    Ok(RotationResult {
+        image: Some(image),
+        report: Some(report),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(from: &str, to: &str, image: &str) -> DateRule {
+        DateRule {
+            from: from.to_string(),
+            to: to.to_string(),
+            image: image.to_string(),
+        }
+    }
+
+    fn config(
+        policy: RotationPolicy,
+        rules: Vec<DateRule>,
+        default: Option<&str>,
+    ) -> RotationConfig {
+        RotationConfig {
+            library: PathBuf::from("logos"),
+            policy,
+            resize: ResizePolicy::default(),
+            sanitize: true,
+            default: default.map(str::to_string),
+            rules,
+        }
+    }
+
+    fn images(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn rule_matches_inclusive_range() {
+        let summer = rule("06-01", "08-31", "summer.png");
+        assert!(rule_matches(&summer, (6, 1)));
+        assert!(rule_matches(&summer, (7, 15)));
+        assert!(rule_matches(&summer, (8, 31)));
+        assert!(!rule_matches(&summer, (5, 31)));
+        assert!(!rule_matches(&summer, (9, 1)));
+    }
+
+    #[test]
+    fn rule_matches_wraps_across_new_year() {
+        let christmas = rule("12-20", "01-02", "christmas.png");
+        assert!(rule_matches(&christmas, (12, 20)));
+        assert!(rule_matches(&christmas, (12, 31)));
+        assert!(rule_matches(&christmas, (1, 1)));
+        assert!(rule_matches(&christmas, (1, 2)));
+        assert!(!rule_matches(&christmas, (1, 3)));
+        assert!(!rule_matches(&christmas, (12, 19)));
+        assert!(!rule_matches(&christmas, (6, 15)));
+    }
+
+    #[test]
+    fn sequential_picks_next_and_wraps() {
+        let config = config(RotationPolicy::Sequential, Vec::new(), None);
+        let images = images(&["a.png", "b.png", "c.png"]);
+        let pick_after = |last| pick(&config, &images, last, (1, 1)).unwrap();
+        assert_eq!(pick_after(None).as_deref(), Some("a.png"));
+        assert_eq!(pick_after(Some("a.png")).as_deref(), Some("b.png"));
+        assert_eq!(pick_after(Some("c.png")).as_deref(), Some("a.png"));
+        // 上次的图片已从图片库删除时从头开始
+        assert_eq!(pick_after(Some("gone.png")).as_deref(), Some("a.png"));
+        assert!(pick(&config, &[], None, (1, 1)).is_err());
+    }
+
+    #[test]
+    fn date_picks_first_matching_rule_or_default() {
+        let rules = vec![
+            rule("12-20", "01-02", "christmas.png"),
+            rule("12-01", "12-31", "december.png"),
+        ];
+        let with_default = config(RotationPolicy::Date, rules, Some("lenovo.png"));
+        let pick_on = |today| pick(&with_default, &[], None, today).unwrap();
+        assert_eq!(pick_on((12, 24)).as_deref(), Some("christmas.png"));
+        assert_eq!(pick_on((12, 10)).as_deref(), Some("december.png"));
+        assert_eq!(pick_on((1, 1)).as_deref(), Some("christmas.png"));
+        assert_eq!(pick_on((3, 1)).as_deref(), Some("lenovo.png"));
+
+        let without_default = config(
+            RotationPolicy::Date,
+            vec![rule("12-20", "01-02", "christmas.png")],
+            None,
+        );
+        assert_eq!(pick(&without_default, &[], None, (3, 1)).unwrap(), None);
+    }
+}
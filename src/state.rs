@@ -0,0 +1,201 @@
+//! 已应用状态模块
+//!
+//! 界面、命令行、辅助进程和apply每次成功设置Logo后，把写入的图片和LBLDESP/LBLDVC保存到状态目录
+//! （`PlatformInfo` 本身不写状态，库的其他使用者不受影响）。
+//! BIOS更新经常会清除LBLDESP的启用标志或LBLDVC中的校验值，开机检查时与保存的状态比较，
+//! 发现被重置后用保存的图片重新设置。
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::imaging::{self, ResizePolicy};
+use crate::lenlogo::{PlatformInfo, VerifyStatus};
+use crate::platform::{NativePlatform, PlatformOps};
+
+/// 状态文件的格式版本
+const STATE_VERSION: u32 = 1;
+
+/// 状态文件名
+const STATE_FILE: &str = "state.json";
+
+/// 上次成功设置Logo时的状态
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedState {
+    /// 状态文件格式版本
+    pub version: u32,
+    /// 图片扩展名
+    pub ext: String,
+    /// 写入ESP的图片的SHA-256
+    pub sha256: String,
+    /// 写入后的LBLDESP（hex）
+    pub lbldesp: String,
+    /// 写入后的LBLDVC（hex）
+    pub lbldvc: String,
+    /// 写入时间（Unix时间戳，秒）
+    pub applied_at: u64,
+}
+
+fn state_path() -> PathBuf {
+    NativePlatform::state_dir().join(STATE_FILE)
+}
+
+fn image_path(ext: &str) -> PathBuf {
+    NativePlatform::state_dir().join(format!("logo.{}", ext))
+}
+
+/// 记录刚写入的图片和变量
+pub fn record(data: &[u8], ext: &str, platform_info: &PlatformInfo) -> Result<(), String> {
+    let dir = NativePlatform::state_dir();
+    std::fs::create_dir_all(&dir).map_err(|err| format!("create {}: {}", dir.display(), err))?;
+
+    // 先删除上次保存的图片，扩展名可能不同
+    if let Some(old) = load_state()? {
+        let _ = std::fs::remove_file(image_path(&old.ext));
+    }
+
+    let image = image_path(ext);
+    std::fs::write(&image, data).map_err(|err| format!("write {}: {}", image.display(), err))?;
+
+    let state = AppliedState {
+        version: STATE_VERSION,
+        ext: ext.to_string(),
+        sha256: hex::encode(Sha256::digest(data)),
+        lbldesp: hex::encode(platform_info.lbldesp_var),
+        lbldvc: hex::encode(platform_info.lbldvc_var),
+        applied_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    };
+    let json = serde_json::to_string_pretty(&state).map_err(|err| err.to_string())?;
+
+    // 先写临时文件再重命名，避免断电留下半个状态文件
+    let path = state_path();
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)
+        .and_then(|_| std::fs::rename(&tmp, &path))
+        .map_err(|err| format!("write {}: {}", path.display(), err))?;
+    info!("Recorded applied state in {}", path.display());
+    Ok(())
+}
+
+/// 删除记录，恢复默认Logo后调用，开机检查不再重新设置
+pub fn clear() -> Result<(), String> {
+    if let Some(state) = load_state()? {
+        let _ = std::fs::remove_file(image_path(&state.ext));
+    }
+    match std::fs::remove_file(state_path()) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!("remove {}: {}", state_path().display(), err)),
+    }
+}
+
+fn load_state() -> Result<Option<AppliedState>, String> {
+    let path = state_path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("read {}: {}", path.display(), err)),
+    };
+    let state: AppliedState =
+        serde_json::from_str(&text).map_err(|err| format!("parse {}: {}", path.display(), err))?;
+    if state.version != STATE_VERSION {
+        return Err(format!("unsupported state version {}", state.version));
+    }
+    Ok(Some(state))
+}
+
+/// 读取记录的状态和保存的图片，没有记录时返回None
+pub fn load() -> Result<Option<(AppliedState, Vec<u8>)>, String> {
+    let Some(state) = load_state()? else {
+        return Ok(None);
+    };
+    let image = image_path(&state.ext);
+    let data = std::fs::read(&image).map_err(|err| format!("read {}: {}", image.display(), err))?;
+    if hex::encode(Sha256::digest(&data)) != state.sha256 {
+        return Err(format!(
+            "{} does not match the recorded hash",
+            image.display()
+        ));
+    }
+    Ok(Some((state, data)))
+}
+
+/// 开机检查的结果
+#[derive(Debug)]
+pub enum CheckOutcome {
+    /// 没有记录，不需要检查（从未设置过或已恢复默认Logo）
+    NothingRecorded,
+    /// 固件中的设置与记录一致
+    Intact,
+    /// 检测到被重置，已重新设置
+    Reapplied {
+        /// 检测到的变化
+        reasons: Vec<String>,
+    },
+}
+
+/// 比较当前固件状态与记录的状态，被重置时用保存的图片重新设置（需要管理员/root权限）
+pub fn check() -> Result<CheckOutcome, String> {
+    let Some((state, data)) = load()? else {
+        return Ok(CheckOutcome::NothingRecorded);
+    };
+
+    let mut platform_info = PlatformInfo::default();
+    platform_info
+        .get_info()
+        .map_err(|diag| format!("device not supported: {}", diag))?;
+
+    let mut reasons = Vec::new();
+    if platform_info.enable == 0 {
+        reasons.push("LBLDESP enable flag was cleared".to_string());
+    }
+    if hex::encode(platform_info.lbldvc_var) != state.lbldvc {
+        reasons.push("LBLDVC checksum differs from the recorded value".to_string());
+    }
+    if reasons.is_empty() {
+        match platform_info.verify_logo() {
+            VerifyStatus::Ok { .. } => return Ok(CheckOutcome::Intact),
+            status => reasons.push(format!("installed logo: {}", status)),
+        }
+    }
+    for reason in &reasons {
+        warn!("Firmware logo settings were reset: {}", reason);
+    }
+
+    // BIOS更新可能改变最大分辨率或校验方式，重新准备图片并计算校验值
+    if !platform_info.support.contains(&state.ext.as_str()) {
+        return Err(format!(
+            "format {} is no longer supported by the firmware",
+            state.ext
+        ));
+    }
+    let data = imaging::prepare(
+        data,
+        &state.ext,
+        ResizePolicy::Fit,
+        platform_info.width,
+        platform_info.height,
+    )?;
+    if !platform_info.set_logo_bytes(&data, &state.ext) {
+        return Err("re-apply logo failed".to_string());
+    }
+    if let Err(err) = record(&data, &state.ext, &platform_info) {
+        warn!("record applied state failed: {}", err);
+    }
+
+    let mut updated_info = PlatformInfo::default();
+    updated_info
+        .get_info()
+        .map_err(|diag| format!("re-read variables failed: {}", diag))?;
+    match updated_info.verify_logo() {
+        VerifyStatus::Ok { file } => info!("Re-applied logo, verified {}", file),
+        status => return Err(format!("verification after re-apply failed: {}", status)),
+    }
+    Ok(CheckOutcome::Reapplied { reasons })
+}
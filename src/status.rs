@@ -0,0 +1,214 @@
+//! 机器可读的状态输出
+//!
+//! 命令行工具的 `--json` 输出，格式说明见 docs/json-output.md。
+//! 同一个 `schema_version` 内只会增加字段，不会删除或改变已有字段的含义。
+
+use serde::Serialize;
+
+use crate::confirm::Confirmation;
+use crate::esp_partition::read_logo_files;
+use crate::installed::InstalledLogo;
+use crate::lenlogo::{LOGO_DIR, PlatformInfo, SupportDiagnosis, VerifyStatus};
+
+/// JSON输出的格式版本，出现不兼容修改时加1
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 状态报告，对应JSON输出的顶层对象
+#[derive(Serialize)]
+pub struct StatusReport {
+    /// 格式版本，见 [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// 工具版本
+    pub tool_version: &'static str,
+    /// 设备是否支持修改Logo
+    pub supported: bool,
+    /// 不支持的原因，支持时为 `null`
+    pub diagnosis: Option<DiagnosisJson>,
+    /// 固件中的Logo设置，变量无法读取时为 `null`
+    pub platform: Option<PlatformJson>,
+    /// ESP中已安装的Logo文件，未读取ESP时为 `null`
+    pub installed_logo: Option<Vec<LogoFileJson>>,
+    /// 校验结果，未执行校验时为 `null`
+    pub verify: Option<VerifyJson>,
+    /// 上次开机是否显示了自定义Logo，未检查时为 `null`
+    pub boot_confirmation: Option<ConfirmationJson>,
+    /// 命令无法执行的原因（如没有管理员/root权限），正常时为 `null`
+    pub error: Option<String>,
+}
+
+/// 不支持的原因
+#[derive(Serialize)]
+pub struct DiagnosisJson {
+    /// 稳定的原因代码，如 `not_uefi`、`lbldesp_missing`
+    pub code: &'static str,
+    /// 英文说明
+    pub message: String,
+}
+
+/// `PlatformInfo` 的所有字段
+#[derive(Serialize)]
+pub struct PlatformJson {
+    /// 自定义Logo是否启用
+    pub enable: bool,
+    /// Logo最大宽度
+    pub width: u32,
+    /// Logo最大高度
+    pub height: u32,
+    /// 校验方式版本，如 `0x20003`
+    pub version: String,
+    /// 校验方式：`sha256`、`crc32`，未知版本为 `null`
+    pub checksum: Option<&'static str>,
+    /// 固件支持的图片格式
+    pub support: Vec<&'static str>,
+    /// LBLDESP原始内容（hex）
+    pub lbldesp: String,
+    /// LBLDESP的变量属性
+    pub lbldesp_attributes: u32,
+    /// LBLDVC原始内容（hex）
+    pub lbldvc: String,
+    /// LBLDVC的变量属性
+    pub lbldvc_attributes: u32,
+}
+
+/// ESP中的一个Logo文件
+#[derive(Serialize)]
+pub struct LogoFileJson {
+    /// ESP中的路径
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: usize,
+    /// 文件的SHA-256（hex）
+    pub sha256: String,
+    /// 文件是否与LBLDVC中的校验值一致
+    pub matches_lbldvc: bool,
+}
+
+/// 校验结果
+#[derive(Serialize)]
+pub struct VerifyJson {
+    /// 结果代码：`ok`、`not_enabled`、`no_logo_file`、`checksum_mismatch`、`esp_unavailable`
+    pub result: &'static str,
+    /// 英文说明
+    pub message: String,
+    /// 校验通过的文件路径，仅 `ok` 时有值
+    pub file: Option<String>,
+}
+
+/// 开机确认结果
+#[derive(Serialize)]
+pub struct ConfirmationJson {
+    /// 结果代码：`confirmed`、`rejected`、`not_rebooted`、`nothing_recorded`、`unknown`
+    pub result: &'static str,
+    /// 英文说明
+    pub message: String,
+    /// BGRT图片与已安装Logo的相似度（0~1），未比较时为 `null`
+    pub similarity: Option<f32>,
+}
+
+impl StatusReport {
+    /// 读取平台信息生成报告，不读取ESP
+    pub fn collect() -> (Self, Option<PlatformInfo>) {
+        let mut platform_info = PlatformInfo::default();
+        match platform_info.get_info() {
+            Ok(()) => {
+                let report = Self::new(None, Some(&platform_info));
+                (report, Some(platform_info))
+            }
+            // 变量已经读到，只是格式掩码或版本不被支持，仍然输出原始内容
+            Err(
+                diag @ (SupportDiagnosis::ZeroFormatMask | SupportDiagnosis::UnknownVersion(_)),
+            ) => (Self::new(Some(&diag), Some(&platform_info)), None),
+            Err(diag) => (Self::new(Some(&diag), None), None),
+        }
+    }
+
+    fn new(diag: Option<&SupportDiagnosis>, platform_info: Option<&PlatformInfo>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            supported: diag.is_none(),
+            diagnosis: diag.map(|diag| DiagnosisJson {
+                code: diag.i18n_key().trim_start_matches("diag_"),
+                message: diag.to_string(),
+            }),
+            platform: platform_info.map(PlatformJson::from),
+            installed_logo: None,
+            verify: None,
+            boot_confirmation: None,
+            error: None,
+        }
+    }
+
+    /// 填入校验结果
+    pub fn set_verify(&mut self, status: &VerifyStatus) {
+        self.verify = Some(VerifyJson {
+            result: status.code(),
+            message: status.to_string(),
+            file: match status {
+                VerifyStatus::Ok { file } => Some(format!("{}/{}", LOGO_DIR, file)),
+                _ => None,
+            },
+        });
+    }
+
+    /// 填入开机确认结果
+    pub fn set_confirmation(&mut self, confirmation: &Confirmation) {
+        self.boot_confirmation = Some(ConfirmationJson {
+            result: confirmation.code(),
+            message: confirmation.to_string(),
+            similarity: confirmation.similarity(),
+        });
+    }
+
+    /// 读取ESP中的Logo文件并校验，只挂载一次ESP（需要管理员/root权限）
+    pub fn read_esp(&mut self, platform_info: &PlatformInfo) {
+        let files = read_logo_files();
+        self.set_verify(&platform_info.verify_files(files.as_deref()));
+        if let Some(files) = files {
+            self.installed_logo = Some(
+                InstalledLogo::from_files(files, platform_info)
+                    .iter()
+                    .map(LogoFileJson::from)
+                    .collect(),
+            );
+        }
+    }
+
+    /// 输出格式化的JSON
+    pub fn to_json(&self) -> String {
+        // 只包含字符串、数字和数组，序列化不会失败
+        serde_json::to_string_pretty(self).expect("status report serializes to JSON")
+    }
+}
+
+impl From<&InstalledLogo> for LogoFileJson {
+    fn from(logo: &InstalledLogo) -> Self {
+        Self {
+            path: logo.path(),
+            size: logo.data.len(),
+            sha256: logo.sha256.clone(),
+            matches_lbldvc: logo.matches_lbldvc,
+        }
+    }
+}
+
+impl From<&PlatformInfo> for PlatformJson {
+    fn from(info: &PlatformInfo) -> Self {
+        Self {
+            enable: info.enable != 0,
+            width: info.width,
+            height: info.height,
+            version: format!("0x{:x}", info.version),
+            checksum: match info.version {
+                0x20003 => Some("sha256"),
+                0x20000 => Some("crc32"),
+                _ => None,
+            },
+            support: info.support.clone(),
+            lbldesp: hex::encode(info.lbldesp_var),
+            lbldesp_attributes: info.lbldesp_attr.bits(),
+            lbldvc: hex::encode(info.lbldvc_var),
+            lbldvc_attributes: info.lbldvc_attr.bits(),
+        }
+    }
+}